@@ -124,7 +124,8 @@ fn execute_action_add_command(context: &ResolvedContext, matches: &ArgMatches) -
             name: name.clone(),
             path: relative_path.to_string(),
             about: None,
-            args: vec![]
+            args: vec![],
+            depends_on: vec![]
         }
     ];
 
@@ -148,7 +149,7 @@ fn execute_action_add_command(context: &ResolvedContext, matches: &ArgMatches) -
 }
 
 fn execute_action_list_command(context: &ResolvedContext, matches: &ArgMatches) -> Result<(), Box<dyn Error>> {
-    let commands = list_actions(context);
+    let commands = list_actions(context)?;
 
     let mut table = Table::new();
 
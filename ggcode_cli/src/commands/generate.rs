@@ -1,4 +1,5 @@
 use std::error::Error;
+use std::sync::{Arc, Mutex};
 
 use clap::{Arg, arg, ArgMatches, Command};
 
@@ -7,6 +8,8 @@ use ggcode_core::generator::DefaultGenerator;
 use ggcode_core::scroll::{list_scrolls, ScrollRef};
 use ggcode_core::storage::resolve_target;
 
+use crate::terminal::observer::ConsoleObserver;
+
 pub fn create_generate_command(context: &Context) -> Result<Command, Box<dyn Error>> {
     let mut command = Command::new("generate")
         .about("Execute generation script from scroll")
@@ -14,7 +17,7 @@ pub fn create_generate_command(context: &Context) -> Result<Command, Box<dyn Err
         .arg_required_else_help(true);
 
     if let Some(resolved_context) = context.resolve().ok() {
-        let scrolls = list_scrolls(&resolved_context);
+        let scrolls = list_scrolls(&resolved_context)?;
         for (_, scroll) in scrolls {
             let subcommand = create_generate_scroll_command(&resolved_context, &scroll)?;
             command = command.subcommand(subcommand);
@@ -41,11 +44,6 @@ pub fn create_generate_scroll_command(
             .required_unless_present("target"))
         .arg(arg!(-v --variables <path> "Path to a file or directory containing variable overrides")
             .required(false))
-        .arg(Arg::new("dry-run")
-            .long("dry-run".to_string())
-            .short('d')
-            .num_args(0)
-            .help("Do not generate files; simply test the ability to render templates"))
         .arg_required_else_help(true);
 
     Ok(command)
@@ -62,19 +60,23 @@ pub fn execute_generate_scroll_command(context: &ResolvedContext, name: &String,
     let target_name = matches.get_one::<String>("target");
     let target_path = matches.get_one::<String>("target-path");
 
-    let dry_run = matches.get_one::<bool>("dry-run").unwrap();
+    let dry_run = matches.get_flag("dry-run");
 
     let resolved_target_path = resolve_target(
         &context,
         target_name.map(|d| d.clone()),
         target_path.map(|d| d.clone()))?;
 
-    let generator = DefaultGenerator {
+    let mut generator = DefaultGenerator {
         context: context.clone(),
         wrapped_observers: vec![]
     };
+    // Rendered templates are only listed on a dry run
+    if dry_run {
+        generator.add_observer(Arc::new(Mutex::new(ConsoleObserver::default())));
+    }
 
-    generator.generate(name, &resolved_target_path, *dry_run, None)?;
+    generator.generate(name, &resolved_target_path, dry_run, None)?;
 
     Ok(())
 }
@@ -75,9 +75,11 @@ pub fn execute_init_command(context: &Context, matches: &ArgMatches) -> Result<(
         actions: chains,
         targets,
         repositories,
+        limits: context.current_config.as_ref().and_then(|config| config.limits.clone()),
     };
 
-    save_config(&resolve_inner_path(&context.config_path)?, config)
+    save_config(&resolve_inner_path(&context.config_path)?, config)?;
+    Ok(())
 }
 
 fn setup_scrolls(config: &Option<PackageConfig>, _matches: &ArgMatches) -> Result<Vec<ScrollEntry>, Box<dyn Error>> {
@@ -113,7 +115,8 @@ fn setup_repositories(config: &Option<PackageConfig>, matches: &ArgMatches) -> R
                 Some(v) => {
                     repositories.push(RepositoryEntry {
                         name: v.name("name").unwrap().as_str().to_string(),
-                        uri: v.name("uri").unwrap().as_str().to_string()
+                        uri: v.name("uri").unwrap().as_str().to_string(),
+                        capabilities: vec![],
                     })
                 }
             }
@@ -149,6 +152,7 @@ fn setup_repositories(config: &Option<PackageConfig>, matches: &ArgMatches) -> R
                 repositories.push(RepositoryEntry {
                     name: REPOSITORY_CORE_NAME.to_string(),
                     uri: REPOSITORY_CORE_URI.to_string(),
+                    capabilities: vec![],
                 })
             }
         }
@@ -180,7 +184,8 @@ fn setup_repositories(config: &Option<PackageConfig>, matches: &ArgMatches) -> R
             if added {
                 repositories.push(RepositoryEntry {
                     name,
-                    uri
+                    uri,
+                    capabilities: vec![],
                 });
             }
 
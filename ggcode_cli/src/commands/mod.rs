@@ -1,6 +1,7 @@
 use std::error::Error;
 
-use clap::{ArgMatches, Command, command};
+use clap::{Arg, ArgAction, ArgMatches, Command, command};
+use clap::builder::FalseyValueParser;
 
 use ggcode_core::Context;
 
@@ -30,6 +31,24 @@ pub fn create_cli_command(context: &Context) -> Result<Command, Box<dyn Error>>
         .arg_required_else_help(true)
         .allow_external_subcommands(true)
         .subcommand_required(false)
+        .arg(Arg::new("dry-run")
+            .long("dry-run")
+            .short('d')
+            .num_args(0)
+            .global(true)
+            .help("Do not change any files; simply report what would be done"))
+        .arg(Arg::new("verbose")
+            .long("verbose")
+            .num_args(0)
+            .global(true)
+            .help("Print more details about failures, like the traceback of scripts"))
+        .arg(Arg::new("no-input")
+            .long("no-input")
+            .action(ArgAction::SetTrue)
+            .value_parser(FalseyValueParser::new())
+            .env("GGCODE_NO_INPUT")
+            .global(true)
+            .help("Never prompt; scripts use the default answers and fail on questions without one"))
         .subcommand(create_init_command())
         .subcommand(create_install_command())
         .subcommand(create_generate_command(context)?)
@@ -60,3 +79,67 @@ pub fn execute_cli_command(context: &Context, matches: &ArgMatches) -> Result<()
         _ => return Err("Unsupported command".into())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use ggcode_core::Context;
+    use ggcode_core::config::PackageConfig;
+
+    use crate::commands::create_cli_command;
+
+    #[test]
+    fn cli_command_test() -> Result<(), Box<dyn std::error::Error>> {
+        let config = serde_yaml::from_str::<PackageConfig>(indoc::indoc! {r#"
+            name: app
+            scrolls:
+            - name: model
+              path: scrolls/model
+            actions:
+            - name: deploy
+              path: actions/deploy.luau
+              args:
+              - name: force
+                kind: boolean
+              - name: stage
+                kind: enum
+                choices: [dev, prod]
+              - name: direction
+                positional: true
+        "#})?;
+        let context = Context {
+            config_path: "ggcode-info.yaml".to_string(),
+            directory_path: env::current_dir()?,
+            directory_name: "app".to_string(),
+            current_config: Some(config),
+        };
+
+        // Clashing names and shorts of nested commands are only reported by the debug asserts
+        create_cli_command(&context)?.debug_assert();
+        Ok(())
+    }
+
+    #[test]
+    fn cli_command_reserved_short_test() -> Result<(), Box<dyn std::error::Error>> {
+        let config = serde_yaml::from_str::<PackageConfig>(indoc::indoc! {r#"
+            name: app
+            actions:
+            - name: deploy
+              path: actions/deploy.luau
+              args:
+              - name: destination
+                short: d
+        "#})?;
+        let context = Context {
+            config_path: "ggcode-info.yaml".to_string(),
+            directory_path: env::current_dir()?,
+            directory_name: "app".to_string(),
+            current_config: Some(config),
+        };
+
+        let error = create_cli_command(&context).unwrap_err();
+        assert!(error.to_string().contains("Argument destination of action @/deploy cannot use the short -d"));
+        Ok(())
+    }
+}
@@ -110,6 +110,7 @@ fn execute_repository_add_command(context: ResolvedContext, matches: &ArgMatches
         let repositories = vec![RepositoryEntry {
             name: name.to_string(),
             uri: uri.to_string(),
+            capabilities: vec![],
         }];
         let config = PackageConfig {
             repositories: [&context.current_config.repositories[..], &repositories[..]].concat(),
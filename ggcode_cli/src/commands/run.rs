@@ -1,18 +1,20 @@
-use std::env;
 use std::error::Error;
+use std::io::{self, IsTerminal};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
 
-use clap::{Arg, ArgMatches, Command};
-use console::style;
+use clap::{Arg, ArgAction, ArgMatches, Command, value_parser};
+use clap::builder::{FalseyValueParser, PossibleValuesParser};
 use convert_case::{Case, Casing};
-use relative_path::RelativePathBuf;
 use serde_yaml::{Mapping, Value};
 
 use ggcode_core::{Context, ResolvedContext};
-use ggcode_core::action::{ActionRef, find_action_by_full_name, list_actions};
+use ggcode_core::action::{ActionRef, ActionRunner, find_action_by_full_name, list_actions, PASSTHROUGH_ARG};
+use ggcode_core::config::{ActionArg, ActionArgKind};
 use ggcode_core::generator::DefaultGenerator;
-use ggcode_core::renderer::luau_evaluator::LuauEvaluatorBuilder;
-use ggcode_core::renderer::luau_extras::{LuauEngine, LuauShell};
-use ggcode_core::storage::{load_string, resolve_search_locations};
+
+use crate::terminal::observer::ConsoleObserver;
+use crate::terminal::prompter::TerminalPrompter;
 
 pub fn create_run_command(context: &Context) -> Result<Command, Box<dyn Error>> {
     let mut command = Command::new("run")
@@ -21,7 +23,7 @@ pub fn create_run_command(context: &Context) -> Result<Command, Box<dyn Error>>
         .arg_required_else_help(true);
 
     if let Some(resolved_context) = context.resolve().ok() {
-        let actions = list_actions(&resolved_context);
+        let actions = list_actions(&resolved_context)?;
         for (name, action) in actions {
             let subcommand = create_run_action_command(&resolved_context, &name, &action)?;
             command = command.subcommand(subcommand);
@@ -40,16 +42,114 @@ pub fn create_run_action_command(
         .about(&action.action.about.clone().unwrap_or(format!("Run {} action", &action.action.name)));
 
     for arg_entry in &action.action.args {
-        let arg = Arg::new(&arg_entry.name)
-            .long(&arg_entry.name)
-            .help(arg_entry.about.clone().unwrap_or("Magical spell option".to_string()))
-            .required(arg_entry.required.unwrap_or(false));
-        command = command.arg(arg);
+        command = command.arg(create_action_arg(action_name, arg_entry)?);
     }
 
+    // Everything after `--` is passed on untouched, so actions can wrap other tools
+    command = command.arg(Arg::new(PASSTHROUGH_ARG)
+        .value_name("ARGS")
+        .num_args(0..)
+        .last(true)
+        .allow_hyphen_values(true)
+        .help("Arguments passed to the action as they are"));
+
     Ok(command)
 }
 
+/// Options of the CLI shared by every command, which action arguments cannot take over.
+const RESERVED_OPTIONS: [(Option<char>, &str); 4] = [
+    (Some('d'), "dry-run"),
+    (None, "verbose"),
+    (None, "no-input"),
+    (Some('h'), "help"),
+];
+
+/// Builds the option of an action argument, its value is parsed according to the argument kind.
+fn create_action_arg(action_name: &str, arg_entry: &ActionArg) -> Result<Arg, Box<dyn Error>> {
+    let positional = arg_entry.positional.unwrap_or(false);
+    for (short, long) in RESERVED_OPTIONS {
+        if !positional && arg_entry.name == long {
+            return Err(format!("Argument {} of action {} clashes with the --{} option. Rename the argument.", arg_entry.name, action_name, long).into());
+        }
+        if !positional && short.is_some() && arg_entry.short == short {
+            return Err(format!("Argument {} of action {} cannot use the short -{}, it is reserved for --{}.", arg_entry.name, action_name, short.unwrap_or_default(), long).into());
+        }
+    }
+
+    let kind = arg_entry.kind.unwrap_or(ActionArgKind::String);
+    let mut arg = Arg::new(&arg_entry.name)
+        .help(arg_entry.about.clone().unwrap_or("Magical spell option".to_string()))
+        .required(arg_entry.required.unwrap_or(false));
+
+    if !positional {
+        arg = arg.long(&arg_entry.name);
+    }
+
+    arg = match kind {
+        ActionArgKind::String => arg.value_parser(value_parser!(String)),
+        ActionArgKind::Boolean => arg.value_parser(FalseyValueParser::new()),
+        ActionArgKind::Integer => arg.value_parser(value_parser!(i64)),
+        ActionArgKind::Float => arg.value_parser(value_parser!(f64)),
+        ActionArgKind::Enum => arg.value_parser(PossibleValuesParser::new(arg_entry.choices.clone())),
+        ActionArgKind::Path => arg.value_parser(value_parser!(PathBuf)),
+    };
+
+    arg = match (kind, arg_entry.multiple.unwrap_or(false), positional) {
+        // Positional booleans take an explicit value, e.g. `true` or `no`
+        (ActionArgKind::Boolean, _, false) => arg.action(ArgAction::SetTrue),
+        (_, true, true) => arg.action(ArgAction::Append).num_args(1..),
+        (_, true, false) => arg.action(ArgAction::Append),
+        (_, false, _) => arg.action(ArgAction::Set),
+    };
+
+    if let (Some(short), false) = (arg_entry.short, positional) {
+        arg = arg.short(short);
+    }
+    if let Some(env) = &arg_entry.env {
+        arg = arg.env(env);
+    }
+    if let Some(default) = &arg_entry.default {
+        arg = arg.default_values(default_strings(default));
+    }
+    Ok(arg)
+}
+
+fn default_strings(default: &Value) -> Vec<String> {
+    match default {
+        Value::Sequence(values) => values.iter().flat_map(default_strings).collect(),
+        Value::String(value) => vec![value.clone()],
+        Value::Null => vec![],
+        value => vec![serde_yaml::to_string(value).unwrap_or_default().trim_end().to_string()],
+    }
+}
+
+/// Reads the parsed value of an action argument as the Luau value passed in `args`.
+/// Multiple arguments are always lists, possibly empty.
+fn read_action_arg(arg_entry: &ActionArg, matches: &ArgMatches) -> Option<Value> {
+    let name = arg_entry.name.as_str();
+    let values: Vec<Value> = match arg_entry.kind.unwrap_or(ActionArgKind::String) {
+        ActionArgKind::Boolean => matches.get_many::<bool>(name)
+            .map(|values| values.map(|v| Value::from(*v)).collect())
+            .unwrap_or_default(),
+        ActionArgKind::Integer => matches.get_many::<i64>(name)
+            .map(|values| values.map(|v| Value::from(*v)).collect())
+            .unwrap_or_default(),
+        ActionArgKind::Float => matches.get_many::<f64>(name)
+            .map(|values| values.map(|v| Value::from(*v)).collect())
+            .unwrap_or_default(),
+        ActionArgKind::Path => matches.get_many::<PathBuf>(name)
+            .map(|values| values.map(|v| Value::from(v.to_string_lossy().to_string())).collect())
+            .unwrap_or_default(),
+        ActionArgKind::String | ActionArgKind::Enum => matches.get_many::<String>(name)
+            .map(|values| values.map(|v| Value::from(v.clone())).collect())
+            .unwrap_or_default(),
+    };
+    match arg_entry.multiple.unwrap_or(false) {
+        true => Some(Value::Sequence(values)),
+        false => values.into_iter().next(),
+    }
+}
+
 pub fn execute_run_command(context: &ResolvedContext, matches: &ArgMatches) -> Result<(), Box<dyn Error>> {
     match matches.subcommand() {
         Some((path, sub_matches)) => execute_run_action_command(context, &path.to_string(), sub_matches),
@@ -58,58 +158,39 @@ pub fn execute_run_command(context: &ResolvedContext, matches: &ArgMatches) -> R
 }
 
 pub fn execute_run_action_command(context: &ResolvedContext, name: &String, matches: &ArgMatches) -> Result<(), Box<dyn Error>> {
-    let current_dir = env::current_dir().unwrap().canonicalize().unwrap();
-
     let action = find_action_by_full_name(context, name)?;
 
-    let mut builder = LuauEvaluatorBuilder::new();
-
     let mut args_value: Value = Value::Mapping(Mapping::new());
 
+    // Required arguments are enforced by the command line parser
+    let mapping = args_value.as_mapping_mut().unwrap();
     for arg in &action.action.args {
-        let value = matches.get_one::<String>(&arg.name);
-        match value {
-            Some(v) => {
-                let mapping = args_value.as_mapping_mut().unwrap();
-                let name = arg.name.to_case(Case::Snake);
-                mapping.insert(name.into(), v.clone().into());
-            },
-            None => {
-                if let Some(required) = arg.required {
-                    if required {
-                        return Err(format!("Invalid usage. Option {} is required", style(&arg.name).yellow()).into())
-                    }
-                }
-            }
+        if let Some(value) = read_action_arg(arg, matches) {
+            let name = arg.name.to_case(Case::Snake);
+            mapping.insert(name.into(), value);
         }
     }
+    let passthrough: Vec<Value> = matches.get_many::<String>(PASSTHROUGH_ARG)
+        .map(|values| values.map(|v| Value::from(v.clone())).collect())
+        .unwrap_or_default();
+    mapping.insert(PASSTHROUGH_ARG.into(), Value::Sequence(passthrough));
 
-    let search_locations = resolve_search_locations(&context.current_config);
+    let dry_run = matches.get_flag("dry-run");
 
-    for rp in search_locations {
-        builder = builder.with_path_entry(&rp.to_path(&current_dir));
-    }
-
-    builder = builder.with_global("args", &args_value);
-
-    let generator = DefaultGenerator {
+    let mut generator = DefaultGenerator {
         context: context.clone(),
         wrapped_observers: vec![]
     };
+    generator.add_observer(Arc::new(Mutex::new(ConsoleObserver::default())));
 
-    builder = builder.enable_shell(LuauShell);
-
-    builder = builder.enable_engine(LuauEngine {
-        context: context.clone(),
-        generator
-    });
-
-    let evaluator = builder.build()?;
+    // Without a terminal there is nobody to answer
+    let interactive = !matches.get_flag("no-input") && io::stdin().is_terminal();
 
-    let action = find_action_by_full_name(&context, name)?;
-    let script = load_string(&action.package, &RelativePathBuf::from(action.action.path))?;
-
-    evaluator.eval_value(&script)?;
+    let mut runner = ActionRunner::new(context, generator, dry_run);
+    if interactive {
+        runner = runner.with_prompter(Arc::new(TerminalPrompter));
+    }
+    runner.run(&action.full_name, &args_value)?;
 
     Ok(())
 }
@@ -1,3 +1,4 @@
+use std::collections::BTreeMap;
 use std::error::Error;
 
 use clap::{arg, ArgAction, ArgMatches, Command};
@@ -140,7 +141,7 @@ fn execute_scroll_add_command(context: &ResolvedContext, matches: &ArgMatches) -
     }
 
     let scroll_entries = vec![
-        ScrollEntry { name, path: relative_path.to_string(), about: None }
+        ScrollEntry { name, path: relative_path.to_string(), about: None, modes: BTreeMap::new() }
     ];
 
     let config = PackageConfig {
@@ -169,7 +170,7 @@ fn execute_scroll_add_command(context: &ResolvedContext, matches: &ArgMatches) -
 }
 
 fn execute_scroll_list_command(context: &ResolvedContext, matches: &ArgMatches) -> Result<(), Box<dyn Error>> {
-    let scrolls = list_scrolls(context);
+    let scrolls = list_scrolls(context)?;
 
     let mut table = Table::new();
 
@@ -5,11 +5,12 @@ use std::process::ExitCode;
 use ::console::style;
 
 use ggcode_core::config::DEFAULT_CONFIG_NAME;
-use ggcode_core::Context;
+use ggcode_core::{Context, Error as CoreError};
 use ggcode_core::storage::{load_config, resolve_inner_path};
 
 use crate::commands::{create_cli_command, execute_cli_command};
 use crate::greetings::generate_wishes;
+use crate::terminal::trace::trace_source_error;
 
 mod commands;
 mod greetings;
@@ -24,7 +25,12 @@ pub fn load_context() -> Result<Context, Box<dyn Error>> {
         .unwrap()
         .to_string();
 
-    let current_config = load_config(&resolve_inner_path(&DEFAULT_CONFIG_NAME.to_string())?).ok();
+    // A missing configuration is expected before `init`, while a broken one should be reported
+    let config_path = resolve_inner_path(&DEFAULT_CONFIG_NAME.to_string())?;
+    let current_config = match config_path.to_path(&directory_path).is_file() {
+        true => Some(load_config(&config_path)?),
+        false => None,
+    };
 
     let context = Context {
         config_path: DEFAULT_CONFIG_NAME.to_string(),
@@ -36,21 +42,30 @@ pub fn load_context() -> Result<Context, Box<dyn Error>> {
     Ok(context)
 }
 
-fn execute() -> Result<(), Box<dyn Error>> {
+fn execute(verbose: &mut bool) -> Result<(), Box<dyn Error>> {
     let context = load_context()?;
     let cli = create_cli_command(&context)?;
     let matches = cli.get_matches();
+    *verbose = matches.get_flag("verbose");
     execute_cli_command(&context, &matches)
 }
 
 fn main() -> ExitCode {
-    match execute() {
+    let mut verbose = false;
+    match execute(&mut verbose) {
         Ok(()) => {
             eprintln!("{} {}", style("[SUCCESS]").green(), generate_wishes());
             ExitCode::SUCCESS
         },
         Err(e) => {
-            eprintln!("{} {}", style("[FAILURE]").red(), e);
+            let message = match e.downcast_ref::<CoreError>() {
+                Some(core_error) => {
+                    trace_source_error(core_error, verbose);
+                    core_error.describe(|s| style(s).yellow().to_string())
+                },
+                None => e.to_string(),
+            };
+            eprintln!("{} {}", style("[FAILURE]").red(), message);
             ExitCode::FAILURE
         }
     }
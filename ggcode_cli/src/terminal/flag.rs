@@ -1,17 +1,18 @@
-use std::error::Error;
-
 use clap::ArgMatches;
 use console::style;
 use derive_builder::Builder;
 use dialoguer::Confirm;
 use dialoguer::theme::ColorfulTheme;
 
+use ggcode_core::Error;
 use ggcode_core::types::AppResult;
 
 #[derive(Builder)]
 #[builder(setter(into))]
 pub struct TerminalFlag<'a> {
-    matches: &'a ArgMatches,
+    /// Without matches, the value is always asked for
+    #[builder(default)]
+    matches: Option<&'a ArgMatches>,
     name: String,
     prompt: String,
     #[builder(default = "self.default_default_value()")]
@@ -30,13 +31,13 @@ impl <'a> TerminalFlag<'a> {
         TerminalFlagBuilder::default()
     }
 
-    pub fn read_bool(&self) -> Result<Option<bool>, Box<dyn Error>> {
+    pub fn read_bool(&self) -> AppResult<Option<bool>> {
         self.read(|s| Ok(s.clone()))
     }
 
     pub fn read<T, F: Fn(bool) -> AppResult<T>>(&self, convert: F) -> AppResult<Option<T>> {
         // let input = self.matches.get_one::<bool>(&self.name.as_str());
-        let input = match self.matches.contains_id(&self.name.as_str()) {
+        let input = match self.matches.is_some_and(|matches| matches.contains_id(&self.name.as_str())) {
             true => Some(true),
             false => None
         };
@@ -54,7 +55,7 @@ impl <'a> TerminalFlag<'a> {
                         None => Confirm::with_theme(&theme)
                             .with_prompt(&self.prompt)
                     };
-                    Some(input.interact()?)
+                    Some(input.interact().map_err(|dialoguer::Error::IO(e)| e)?)
                 }
             };
 
@@ -64,7 +65,7 @@ impl <'a> TerminalFlag<'a> {
                         Ok(target) => return Ok(Some(target)),
                         Err(e) => {
                             match input {
-                                Some(_) => return Err(format!("Invalid input. {}", e).into()),
+                                Some(_) => return Err(Error::InvalidUsage { message: format!("Invalid value for {}. {}", self.name, e) }),
                                 None => eprintln!("{} Invalid input. {}", style("[FAILURE]").red(), e)
                             }
                         },
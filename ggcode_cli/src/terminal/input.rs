@@ -1,17 +1,18 @@
-use std::error::Error;
-
 use clap::ArgMatches;
 use console::style;
 use derive_builder::Builder;
 use dialoguer::Input;
 use dialoguer::theme::ColorfulTheme;
 
+use ggcode_core::Error;
 use ggcode_core::types::AppResult;
 
 #[derive(Builder)]
 #[builder(setter(into))]
 pub struct TerminalInput<'a> {
-    matches: &'a ArgMatches,
+    /// Without matches, the value is always asked for
+    #[builder(default)]
+    matches: Option<&'a ArgMatches>,
     name: String,
     prompt: String,
     #[builder(default = "self.default_default_value()")]
@@ -30,12 +31,12 @@ impl <'a> TerminalInput<'a> {
         TerminalInputBuilder::default()
     }
 
-    pub fn read_string(&self) -> Result<Option<String>, Box<dyn Error>> {
+    pub fn read_string(&self) -> AppResult<Option<String>> {
         self.read(|s| Ok(s.clone()))
     }
 
-    pub fn read<T, F: Fn(&String) -> Result<T, Box<dyn Error>>>(&self, convert: F) -> AppResult<Option<T>> {
-        let path_input = self.matches.get_one::<String>(&self.name.as_str());
+    pub fn read<T, F: Fn(&String) -> AppResult<T>>(&self, convert: F) -> AppResult<Option<T>> {
+        let path_input = self.matches.and_then(|matches| matches.get_one::<String>(&self.name.as_str()));
 
         loop {
             let path_option = match (path_input, &self.required) {
@@ -50,7 +51,7 @@ impl <'a> TerminalInput<'a> {
                         None => Input::with_theme(&theme)
                             .with_prompt(&self.prompt)
                     };
-                    Some(input.interact_text()?)
+                    Some(input.interact_text().map_err(|dialoguer::Error::IO(e)| e)?)
                 }
             };
 
@@ -60,7 +61,7 @@ impl <'a> TerminalInput<'a> {
                         Ok(target) => return Ok(Some(target)),
                         Err(e) => {
                             match path_input {
-                                Some(_) => return Err(format!("Invalid input. {}", e).into()),
+                                Some(_) => return Err(Error::InvalidUsage { message: format!("Invalid value for {}. {}", self.name, e) }),
                                 None => eprintln!("{} Invalid input. {}", style("[FAILURE]").red(), e)
                             }
                         },
@@ -1,3 +1,6 @@
 pub mod input;
 pub mod flag;
-pub mod progress;
\ No newline at end of file
+pub mod observer;
+pub mod progress;
+pub mod prompter;
+pub mod trace;
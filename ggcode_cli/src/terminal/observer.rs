@@ -0,0 +1,46 @@
+use console::{style, StyledObject};
+use indicatif::ProgressBar;
+
+use ggcode_core::generator::{GeneratorEvent, GeneratorObserver};
+
+use crate::greetings::create_progress_bar;
+
+/// Prints the outcome of generator and action operations to the standard error stream.
+/// A spinner is displayed between the start and the end of an operation, while the output
+/// of running commands is printed above it.
+#[derive(Default)]
+pub struct ConsoleObserver {
+    progress_bar: Option<ProgressBar>,
+}
+
+impl ConsoleObserver {
+    fn println(&self, message: String) {
+        match &self.progress_bar {
+            Some(pb) => pb.suspend(|| eprintln!("{}", message)),
+            None => eprintln!("{}", message),
+        }
+    }
+
+    fn finish(&mut self, marker: StyledObject<&str>, message: &String) {
+        if let Some(pb) = self.progress_bar.take() {
+            pb.finish_and_clear();
+        }
+        eprintln!("{} {}", marker, message);
+    }
+}
+
+impl GeneratorObserver for ConsoleObserver {
+    fn on_notify(&mut self, event: &GeneratorEvent) {
+        match event {
+            GeneratorEvent::Start(message) => {
+                let pb = self.progress_bar.get_or_insert_with(create_progress_bar);
+                pb.set_message(message.clone());
+            },
+            GeneratorEvent::Message(message) => self.println(message.clone()),
+            GeneratorEvent::Output(line) => self.println(format!("  {} {}", style("│").dim(), line)),
+            GeneratorEvent::Finish(message) => self.finish(style("[DONE]").green(), message),
+            GeneratorEvent::Skip(message) => self.finish(style("[DRY RUN]").cyan(), message),
+            GeneratorEvent::Failure(message) => self.finish(style("[FAIL]").red(), message),
+        }
+    }
+}
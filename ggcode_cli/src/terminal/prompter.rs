@@ -0,0 +1,67 @@
+use dialoguer::{MultiSelect, Password, Select};
+use dialoguer::theme::ColorfulTheme;
+
+use ggcode_core::Error;
+use ggcode_core::action::Prompter;
+use ggcode_core::types::AppResult;
+
+use crate::terminal::flag::TerminalFlag;
+use crate::terminal::input::TerminalInput;
+
+/// Asks the questions of actions on the terminal.
+#[derive(Default)]
+pub struct TerminalPrompter;
+
+impl Prompter for TerminalPrompter {
+    fn input(&self, prompt: &str, default: Option<String>) -> AppResult<String> {
+        let answer = TerminalInput::builder()
+            .name(prompt)
+            .prompt(prompt)
+            .default_value(default)
+            .required(true)
+            .build()
+            .map_err(|e| Error::InvalidUsage { message: e.to_string() })?
+            .read_string()?;
+        answer.ok_or_else(|| Error::PromptUnavailable { prompt: prompt.to_string() })
+    }
+
+    fn confirm(&self, prompt: &str, default: Option<bool>) -> AppResult<bool> {
+        let answer = TerminalFlag::builder()
+            .name(prompt)
+            .prompt(prompt)
+            .default_value(default)
+            .required(true)
+            .build()
+            .map_err(|e| Error::InvalidUsage { message: e.to_string() })?
+            .read_bool()?;
+        answer.ok_or_else(|| Error::PromptUnavailable { prompt: prompt.to_string() })
+    }
+
+    fn select(&self, prompt: &str, items: &[String], default: usize) -> AppResult<usize> {
+        let theme = ColorfulTheme::default();
+        Ok(Select::with_theme(&theme)
+            .with_prompt(prompt)
+            .items(items)
+            .default(default)
+            .interact()
+            .map_err(|dialoguer::Error::IO(e)| e)?)
+    }
+
+    fn multiselect(&self, prompt: &str, items: &[String], checked: &[bool]) -> AppResult<Vec<usize>> {
+        let theme = ColorfulTheme::default();
+        Ok(MultiSelect::with_theme(&theme)
+            .with_prompt(prompt)
+            .items(items)
+            .defaults(checked)
+            .interact()
+            .map_err(|dialoguer::Error::IO(e)| e)?)
+    }
+
+    fn password(&self, prompt: &str) -> AppResult<String> {
+        let theme = ColorfulTheme::default();
+        Ok(Password::with_theme(&theme)
+            .with_prompt(prompt)
+            .interact()
+            .map_err(|dialoguer::Error::IO(e)| e)?)
+    }
+}
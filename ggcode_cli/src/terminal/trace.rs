@@ -0,0 +1,57 @@
+use std::{cmp, fs};
+
+use console::{Color, style};
+
+use ggcode_core::Error;
+use ggcode_core::utils::errors::{describe_error, ErrorDescription};
+
+/// Prints the lines around the failing one of a Luau script, a Tera template or a YAML file,
+/// when the error points at them. The message itself is left to the failure line printed after.
+/// The Luau traceback is only printed when `verbose` is set.
+pub fn trace_source_error(error: &Error, verbose: bool) {
+    let source_code = match error {
+        Error::Script { source_code, .. } => source_code,
+        Error::Template { location: Some(location), .. } => &location.source_code,
+        Error::Parse { location: Some(location), .. } => &location.source_code,
+        _ => return,
+    };
+    if let Ok(Some(ErrorDescription::SourceError(data))) = describe_error(error) {
+        let source = match data.is_pointed {
+            true => fs::read_to_string(&data.location).unwrap_or(source_code.clone()),
+            false => source_code.clone(),
+        };
+        let vec: Vec<&str> = source.lines().collect();
+        if vec.is_empty() || data.line >= vec.len() {
+            print_traceback(&data.traceback, verbose);
+            return;
+        }
+        let mut area_vec = vec![];
+        let lower = cmp::max(0, data.line as i32 - 1) as usize;
+        let upper = cmp::min(data.line + 1, vec.len() - 1) + 1;
+        for (i, line) in vec.iter().enumerate().take(upper).skip(lower) {
+            let row = format!("{: >6} │ {}", format!("L{}", i + 1), line);
+            let styled = match i == data.line {
+                true => format!("{: <80}", style(row).white().bg(Color::Color256(52))),
+                false => format!("{: <80}", style(row).white().bg(Color::Color256(17))),
+            };
+            area_vec.push(styled.to_string());
+            if let (true, Some(column)) = (i == data.line, data.column) {
+                area_vec.push(format!("{: >6}   {}{}", "", " ".repeat(column), style("^").red().bold()));
+            }
+        }
+        let area_st = area_vec.join("\n");
+        let location = match (data.location.is_empty(), data.column) {
+            (true, _) => String::new(),
+            (false, Some(column)) => format!("{} {}:{}:{}\n", style("-->").dim(), data.location, data.line + 1, column + 1),
+            (false, None) => format!("{} {}:{}\n", style("-->").dim(), data.location, data.line + 1),
+        };
+        eprintln!("\n{}{}\n", location, area_st);
+        print_traceback(&data.traceback, verbose);
+    }
+}
+
+fn print_traceback(traceback: &Option<String>, verbose: bool) {
+    if let (Some(traceback), true) = (traceback, verbose) {
+        eprintln!("{}\n", style(traceback.trim_end()).dim());
+    }
+}
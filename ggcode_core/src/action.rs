@@ -1,28 +1,87 @@
-use std::collections::BTreeMap;
-use std::error::Error;
+use std::collections::{BTreeMap, BTreeSet};
+use std::env;
+use std::sync::{Arc, Mutex};
 
-use crate::config::{ActionEntry, PackageConfig};
+use convert_case::{Case, Casing};
+use relative_path::RelativePathBuf;
+use serde_yaml::{Mapping, Value};
+
+use crate::capability::resolve_sandbox;
+use crate::config::{ActionArgKind, ActionEntry, PackageConfig};
+use crate::error::{Error, ScriptKind};
+use crate::generator::DefaultGenerator;
+use crate::luau::luau_prompt::LuauPrompt;
+use crate::renderer::luau_evaluator::LuauEvaluatorBuilder;
+use crate::renderer::luau_extras::{LuauEngine, LuauFs, LuauLimits, LuauShell};
 use crate::ResolvedContext;
+use crate::storage::{load_repository_config, load_string, resolve_search_locations};
+use crate::types::AppResult;
+
+pub use crate::luau::luau_prompt::Prompter;
+
+/// Name of the list with the arguments given after `--`, available to actions as `args._`.
+pub const PASSTHROUGH_ARG: &str = "_";
 
+#[derive(Debug, Clone)]
 pub struct ActionRef {
     pub package: PackageConfig,
     pub action: ActionEntry,
     pub full_name: String,
+    pub dependency_name: Option<String>,
 }
 
-pub fn list_actions(context: &ResolvedContext) -> BTreeMap<String, ActionRef> {
+impl ActionRef {
+    /// Directory of the package which declares the action, relative to the project directory.
+    pub fn package_path(&self) -> RelativePathBuf {
+        match &self.dependency_name {
+            None => RelativePathBuf::new(),
+            Some(n) => RelativePathBuf::from("ggcode_modules").join(n),
+        }
+    }
+
+    pub fn script_path(&self) -> RelativePathBuf {
+        self.package_path().join(&self.action.path)
+    }
+
+    /// Full name of an action referenced by this one, `@/` names refer to its own package.
+    pub fn resolve_name(&self, name: &str) -> String {
+        match (&self.dependency_name, name.strip_prefix("@/")) {
+            (Some(dependency_name), Some(action_name)) => format!("{}/{}", dependency_name, action_name),
+            _ => name.to_string(),
+        }
+    }
+}
+
+/// Lists the actions of the project and of the installed repositories. Configurations of
+/// repositories which cannot be parsed are reported instead of being skipped.
+pub fn list_actions(context: &ResolvedContext) -> AppResult<BTreeMap<String, ActionRef>> {
     let mut actions: BTreeMap<String, ActionRef> = BTreeMap::new();
 
+    for repository in context.current_config.repositories.iter() {
+        if let Some(repository_config) = load_repository_config(&repository.name)? {
+            for action in &repository_config.actions {
+                let full_name = format!("{}/{}", repository.name, action.name);
+                actions.insert(full_name.clone(), ActionRef {
+                    package: repository_config.clone(),
+                    action: action.clone(),
+                    full_name,
+                    dependency_name: Some(repository.name.clone()),
+                });
+            }
+        }
+    }
+
     for action in &context.current_config.actions {
         let full_name = format!("@/{}", action.name);
         actions.insert(full_name.clone(), ActionRef {
             package: context.current_config.clone(),
             action: action.clone(),
             full_name: full_name.to_string(),
+            dependency_name: None,
         });
     }
 
-    actions
+    Ok(actions)
 }
 
 pub fn find_action_by_name(_context: &ResolvedContext, package: &PackageConfig, name: &String) -> Option<ActionEntry> {
@@ -32,14 +91,349 @@ pub fn find_action_by_name(_context: &ResolvedContext, package: &PackageConfig,
         .map(|e| e.clone())
 }
 
-pub fn find_action_by_full_name(context: &ResolvedContext, name: &String) -> Result<ActionRef, Box<dyn Error>> {
-    let package = &context.current_config;
-    match package.actions.iter().find(|a| name.eq(&format!("@/{}", a.name))) {
-        None => Err(format!("No action with name: {}", name).into()),
-        Some(action) => Ok(ActionRef {
+/// Completes the arguments given to an action with the defaults of its declaration. Keys are
+/// the snake case names of the arguments, multiple arguments are lists.
+pub fn resolve_action_args(action: &ActionEntry, args: &Value) -> AppResult<Value> {
+    let mut mapping = match args {
+        Value::Null => Mapping::new(),
+        // Luau cannot tell an empty table from an empty list
+        Value::Sequence(values) if values.is_empty() => Mapping::new(),
+        Value::Mapping(mapping) => mapping.clone(),
+        _ => return Err(Error::InvalidUsage {
+            message: format!("Arguments of action {} should be a table.", action.name),
+        }),
+    };
+    for arg in &action.args {
+        let key = Value::from(arg.name.to_case(Case::Snake));
+        if mapping.contains_key(&key) {
+            continue;
+        }
+        if arg.required.unwrap_or(false) {
+            return Err(Error::InvalidUsage {
+                message: format!("Missing argument {} of action {}.", key.as_str().unwrap_or_default(), action.name),
+            });
+        }
+        let value = match (&arg.default, arg.multiple.unwrap_or(false)) {
+            (Some(Value::Sequence(values)), true) => Value::Sequence(values.clone()),
+            (Some(value), true) => Value::Sequence(vec![value.clone()]),
+            (Some(value), false) => value.clone(),
+            (None, true) => Value::Sequence(vec![]),
+            (None, false) if arg.kind == Some(ActionArgKind::Boolean) => Value::Bool(false),
+            (None, false) => continue,
+        };
+        mapping.insert(key, value);
+    }
+    let passthrough = Value::from(PASSTHROUGH_ARG);
+    if !mapping.contains_key(&passthrough) {
+        mapping.insert(passthrough, Value::Sequence(vec![]));
+    }
+    Ok(Value::Mapping(mapping))
+}
+
+/// Runs the actions of a single invocation. Dependencies listed in `depends_on` run before
+/// the action and at most once, actions which end up running themselves fail.
+#[derive(Clone)]
+pub struct ActionRunner {
+    pub context: ResolvedContext,
+    pub generator: DefaultGenerator,
+    pub dry_run: bool,
+    /// Asks the user the questions of scripts, without one the default answers are used, see [`LuauPrompt`]
+    pub prompter: Option<Arc<dyn Prompter>>,
+    state: Arc<Mutex<ActionRunState>>,
+}
+
+#[derive(Default)]
+struct ActionRunState {
+    running: Vec<String>,
+    finished: BTreeSet<String>,
+}
+
+impl ActionRunner {
+    pub fn new(context: &ResolvedContext, generator: DefaultGenerator, dry_run: bool) -> ActionRunner {
+        ActionRunner {
+            context: context.clone(),
+            generator,
+            dry_run,
+            prompter: None,
+            state: Arc::new(Mutex::new(ActionRunState::default())),
+        }
+    }
+
+    pub fn with_prompter(mut self, prompter: Arc<dyn Prompter>) -> ActionRunner {
+        self.prompter = Some(prompter);
+        self
+    }
+
+    /// Runs the dependencies of the action, then the action itself with `args`.
+    pub fn run(&self, name: &String, args: &Value) -> AppResult<()> {
+        let action = find_action_by_full_name(&self.context, name)?;
+
+        self.enter(&action.full_name)?;
+        let result = self.run_dependencies(&action)
+            .and_then(|_| self.execute(&action, args));
+        self.leave(&action.full_name, result.is_ok());
+
+        result
+    }
+
+    fn enter(&self, name: &str) -> AppResult<()> {
+        let mut state = self.state.lock().unwrap();
+        if let Some(position) = state.running.iter().position(|running| running.eq(name)) {
+            let mut chain = state.running[position..].to_vec();
+            chain.push(name.to_string());
+            return Err(Error::ActionCycle { chain });
+        }
+        state.running.push(name.to_string());
+        Ok(())
+    }
+
+    fn leave(&self, name: &str, finished: bool) {
+        let mut state = self.state.lock().unwrap();
+        state.running.pop();
+        if finished {
+            state.finished.insert(name.to_string());
+        }
+    }
+
+    fn run_dependencies(&self, action: &ActionRef) -> AppResult<()> {
+        for dependency in &action.action.depends_on {
+            let name = action.resolve_name(dependency);
+            let finished = self.state.lock().unwrap().finished.contains(&name);
+            if !finished {
+                self.run(&name, &Value::Null)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn execute(&self, action: &ActionRef, args: &Value) -> AppResult<()> {
+        let current_dir = env::current_dir().unwrap().canonicalize().unwrap();
+
+        let mut builder = LuauEvaluatorBuilder::new();
+
+        // Modules of the package which declares the action take precedence
+        let mut search_locations = resolve_search_locations(&self.context.current_config);
+        if action.dependency_name.is_some() {
+            search_locations.insert(0, action.package_path().join("lib").join("?.luau"));
+        }
+
+        for rp in search_locations {
+            builder = builder.with_path_entry(&rp.to_path(&current_dir));
+        }
+
+        builder = builder
+            .with_global("args", &resolve_action_args(&action.action, args)?)
+            .with_sandbox(resolve_sandbox(&self.context, action.dependency_name.as_ref()))
+            // Actions may wait for long-running commands, so only the memory is limited
+            .with_kind(ScriptKind::Action)
+            .with_limits(LuauLimits { timeout: None, ..LuauLimits::configured(self.context.current_config.limits.as_ref()) })
+            .enable_shell(LuauShell::default().with_generator(self.generator.clone()))
+            .enable_fs(LuauFs::new(&self.context)?.with_writer(self.generator.clone(), self.dry_run))
+            .enable_engine(LuauEngine {
+                context: self.context.clone(),
+                generator: self.generator.clone(),
+                dry_run: self.dry_run,
+                runner: self.clone(),
+                action: Some(action.clone()),
+                sandbox: None,
+            })
+            .enable_prompt(LuauPrompt::new(self.prompter.clone()));
+
+        let evaluator = builder.build()?;
+
+        let script_path = action.script_path();
+        let script = load_string(&action.package, &script_path)?;
+
+        evaluator.eval_file_value(script_path.as_str(), &script)?;
+
+        Ok(())
+    }
+}
+
+pub fn find_action_by_full_name(context: &ResolvedContext, name: &String) -> AppResult<ActionRef> {
+    list_actions(context)?
+        .remove(name)
+        .ok_or_else(|| Error::ActionNotFound { name: name.clone() })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+    use std::path::PathBuf;
+
+    use indoc::indoc;
+    use serde_yaml::Value;
+
+    use crate::action::{ActionRef, ActionRunner, resolve_action_args};
+    use crate::capability::Sandbox;
+    use crate::config::{Capability, PackageConfig};
+    use crate::error::Error;
+    use crate::generator::DefaultGenerator;
+    use crate::renderer::luau_extras::{GenerationTarget, LuauEngine};
+    use crate::ResolvedContext;
+
+    #[test]
+    fn action_script_path_test() -> Result<(), serde_yaml::Error> {
+        let package = serde_yaml::from_str::<PackageConfig>("name: dep\nactions:\n- name: setup\n  path: actions/setup.luau\n")?;
+        let mut action = ActionRef {
             package: package.clone(),
-            action: action.clone(),
-            full_name: format!("@/{}", action.name),
-        })
+            action: package.actions[0].clone(),
+            full_name: "dep/setup".to_string(),
+            dependency_name: Some("dep".to_string()),
+        };
+        assert_eq!(action.package_path().as_str(), "ggcode_modules/dep");
+        assert_eq!(action.script_path().as_str(), "ggcode_modules/dep/actions/setup.luau");
+        assert_eq!(action.resolve_name("@/format"), "dep/format");
+        assert_eq!(action.resolve_name("other/format"), "other/format");
+
+        action.dependency_name = None;
+        assert_eq!(action.script_path().as_str(), "actions/setup.luau");
+        assert_eq!(action.resolve_name("@/format"), "@/format");
+        Ok(())
+    }
+
+    #[test]
+    fn resolve_action_args_test() -> Result<(), Box<dyn std::error::Error>> {
+        let package = serde_yaml::from_str::<PackageConfig>(indoc! {r#"
+            name: app
+            actions:
+            - name: deploy
+              path: actions/deploy.luau
+              args:
+              - name: force
+                kind: boolean
+              - name: replica-count
+                kind: integer
+                default: 2
+              - name: tags
+                multiple: true
+                default: latest
+              - name: stage
+                required: true
+        "#})?;
+        let action = &package.actions[0];
+
+        let args = resolve_action_args(action, &serde_yaml::from_str("stage: dev\nforce: true")?)?;
+        let expected: Value = serde_yaml::from_str("stage: dev\nforce: true\nreplica_count: 2\ntags: [latest]\n_: []")?;
+        assert_eq!(args, expected);
+
+        let error = resolve_action_args(action, &Value::Null).unwrap_err();
+        assert_eq!(error.to_string(), "Invalid usage. Missing argument stage of action deploy.");
+        Ok(())
+    }
+
+    #[test]
+    fn action_cycle_test() -> Result<(), serde_yaml::Error> {
+        let config = serde_yaml::from_str::<PackageConfig>(indoc! {r#"
+            name: app
+            actions:
+            - name: bootstrap
+              path: actions/bootstrap.luau
+              depends_on: ["@/generate"]
+            - name: generate
+              path: actions/generate.luau
+              depends_on: ["@/format"]
+            - name: format
+              path: actions/format.luau
+              depends_on: ["@/generate"]
+        "#})?;
+        let context = ResolvedContext {
+            config_path: "ggcode-info.yaml".to_string(),
+            directory_path: PathBuf::new(),
+            directory_name: "app".to_string(),
+            current_config: config,
+        };
+        let generator = DefaultGenerator { context: context.clone(), wrapped_observers: vec![] };
+        let runner = ActionRunner::new(&context, generator, true);
+
+        match runner.run(&"@/bootstrap".to_string(), &Value::Null) {
+            Err(Error::ActionCycle { chain }) => assert_eq!(chain, vec!["@/generate", "@/format", "@/generate"]),
+            Err(e) => panic!("Unexpected error: {}", e),
+            Ok(()) => panic!("Cycle was not detected"),
+        }
+        assert!(runner.state.lock().unwrap().running.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn engine_run_in_dependency_test() -> Result<(), serde_yaml::Error> {
+        let config = serde_yaml::from_str::<PackageConfig>(indoc! {r#"
+            name: app
+            actions:
+            - name: secret
+              path: actions/secret.luau
+        "#})?;
+        let dependency = serde_yaml::from_str::<PackageConfig>(indoc! {r#"
+            name: dep
+            actions:
+            - name: setup
+              path: actions/setup.luau
+        "#})?;
+        let context = ResolvedContext {
+            config_path: "ggcode-info.yaml".to_string(),
+            directory_path: PathBuf::new(),
+            directory_name: "app".to_string(),
+            current_config: config,
+        };
+        let generator = DefaultGenerator { context: context.clone(), wrapped_observers: vec![] };
+        let engine = LuauEngine {
+            context: context.clone(),
+            generator: generator.clone(),
+            dry_run: true,
+            runner: ActionRunner::new(&context, generator, true),
+            action: Some(ActionRef {
+                package: dependency.clone(),
+                action: dependency.actions[0].clone(),
+                full_name: "dep/setup".to_string(),
+                dependency_name: Some("dep".to_string()),
+            }),
+            sandbox: None,
+        };
+
+        // The action of the dependency is looked up instead of the trusted one of the project
+        match engine.run("@/secret", None) {
+            Err(Error::ActionNotFound { name }) => assert_eq!(name, "dep/secret"),
+            Err(e) => panic!("Unexpected error: {}", e),
+            Ok(()) => panic!("The action of the project was run"),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn engine_sandbox_write_test() -> Result<(), serde_yaml::Error> {
+        let config = serde_yaml::from_str::<PackageConfig>(indoc! {r#"
+            name: app
+            actions:
+            - name: secret
+              path: actions/secret.luau
+        "#})?;
+        let context = ResolvedContext {
+            config_path: "ggcode-info.yaml".to_string(),
+            directory_path: PathBuf::new(),
+            directory_name: "app".to_string(),
+            current_config: config,
+        };
+        let generator = DefaultGenerator { context: context.clone(), wrapped_observers: vec![] };
+        let engine = LuauEngine {
+            context: context.clone(),
+            generator: generator.clone(),
+            dry_run: false,
+            runner: ActionRunner::new(&context, generator, false),
+            action: None,
+            sandbox: Some(Sandbox { repository: "dep".to_string(), capabilities: BTreeSet::new() }),
+        };
+
+        let target = serde_yaml::from_str::<GenerationTarget>("target_path: out")?;
+        for result in [engine.generate(&"model".to_string(), &target, None).map(|_| ()), engine.run("@/secret", None)] {
+            match result {
+                Err(Error::MissingCapability { capability, repository, .. }) => {
+                    assert_eq!(capability, Capability::FsWrite);
+                    assert_eq!(repository, "dep");
+                }
+                Err(e) => panic!("Unexpected error: {}", e),
+                Ok(()) => panic!("A sandbox without fs-write was allowed to write"),
+            }
+        }
+        Ok(())
     }
 }
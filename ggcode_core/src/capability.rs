@@ -0,0 +1,42 @@
+use std::collections::BTreeSet;
+
+use crate::config::Capability;
+use crate::error::Error;
+use crate::ResolvedContext;
+
+/// Restrictions applied to the Luau scripts of an installed repository.
+#[derive(Debug, Clone)]
+pub struct Sandbox {
+    pub repository: String,
+    pub capabilities: BTreeSet<Capability>,
+}
+
+impl Sandbox {
+    pub fn allows(&self, capability: Capability) -> bool {
+        self.capabilities.contains(&capability)
+    }
+
+    pub fn missing(&self, capability: Capability, operation: &str) -> Error {
+        Error::MissingCapability {
+            capability,
+            repository: self.repository.clone(),
+            operation: operation.to_string(),
+        }
+    }
+}
+
+/// Scripts of the current project are trusted, while scripts of installed repositories only get
+/// the capabilities listed for them in the `repositories` section of the project configuration.
+pub fn resolve_sandbox(context: &ResolvedContext, dependency_name: Option<&String>) -> Option<Sandbox> {
+    let name = dependency_name?;
+    let capabilities = context.current_config.repositories
+        .iter()
+        .find(|repository| repository.name.eq(name))
+        .map(|repository| repository.capabilities.iter().copied().collect())
+        .unwrap_or_default();
+
+    Some(Sandbox {
+        repository: name.clone(),
+        capabilities,
+    })
+}
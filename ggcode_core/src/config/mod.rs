@@ -1,30 +1,64 @@
 use std::collections::BTreeMap;
 
 use serde::{Deserialize, Serialize};
+use serde_yaml::Value;
 
 pub const DEFAULT_CONFIG_NAME: &str = "ggcode-info.yaml";
+pub const DEFAULT_IGNORE_NAME: &str = ".ggcodeignore";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Capability {
+    Shell,
+    FsRead,
+    FsWrite,
+    Env,
+}
+
+impl Capability {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Capability::Shell => "shell",
+            Capability::FsRead => "fs-read",
+            Capability::FsWrite => "fs-write",
+            Capability::Env => "env",
+        }
+    }
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct RepositoryEntry {
     pub name: String,
     pub uri: String,
+    /// Capabilities granted to the Luau scripts of the repository. Scripts of repositories
+    /// without capabilities run in a sandbox.
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub capabilities: Vec<Capability>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct TargetEntry {
     pub name: String,
     pub path: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct ScrollEntry {
     pub name: String,
     pub path: String,
     #[serde(skip_serializing_if = "Option::is_none", default)]
     pub about: Option<String>,
+    /// Unix mode overrides for generated files, keyed by a glob pattern
+    /// matched against the output path, e.g. `"scripts/*.sh": "755"`.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty", default)]
+    pub modes: BTreeMap<String, String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct ActionEntry {
     pub name: String,
     pub path: String,
@@ -32,15 +66,31 @@ pub struct ActionEntry {
     pub about: Option<String>,
     #[serde(skip_serializing_if = "Vec::is_empty", default)]
     pub args: Vec<ActionArg>,
+    /// Actions run before this one, each at most once per invocation. Names starting with `@/`
+    /// refer to actions of the package which declares the action.
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub depends_on: Vec<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Type of an action argument, which decides how it is parsed on the command line
+/// and which Luau type it has in `args`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
 pub enum ActionArgKind {
+    #[serde(alias = "String")]
     String,
+    /// A flag without a value, `false` unless it is given
+    #[serde(alias = "Boolean")]
     Boolean,
+    Integer,
+    Float,
+    /// One of the `choices` of the argument
+    Enum,
+    Path,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct ActionArg {
     pub name: String,
     #[serde(skip_serializing_if = "Option::is_none", default)]
@@ -49,9 +99,45 @@ pub struct ActionArg {
     pub about: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none", default)]
     pub required: Option<bool>,
+    /// Accepts the argument several times, the values are passed as a list
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub multiple: Option<bool>,
+    /// Takes the value by position instead of an option, in the order of declaration.
+    /// Only the last positional argument can be multiple.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub positional: Option<bool>,
+    /// Allowed values of an `enum` argument
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub choices: Vec<String>,
+    /// Value used when the argument is neither given nor set in the environment,
+    /// a list for multiple arguments
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub default: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub short: Option<char>,
+    /// Environment variable read when the argument is not given
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub env: Option<String>,
+}
+
+/// Execution limits of the Luau scripts run for the project. Missing limits keep their
+/// defaults, while zero disables a limit.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LimitsEntry {
+    /// Seconds a variable script or template may spend in Luau, commands run by `shell` excluded
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub timeout: Option<f64>,
+    /// Memory of a Luau state in MiB
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub memory: Option<usize>,
+    /// Function calls and loop iterations of a script
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub interrupts: Option<u64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct PackageConfig {
     pub name: String,
     #[serde(skip_serializing_if = "Vec::is_empty", default)]
@@ -62,9 +148,84 @@ pub struct PackageConfig {
     pub repositories: Vec<RepositoryEntry>,
     #[serde(skip_serializing_if = "Vec::is_empty", default)]
     pub targets: Vec<TargetEntry>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub limits: Option<LimitsEntry>,
 }
 
 pub struct PackageData {
     pub config: PackageConfig,
     pub dependencies: BTreeMap<String, PackageConfig>,
 }
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+
+    use crate::config::{ActionArgKind, PackageConfig};
+    use crate::error::Error;
+    use crate::utils::errors::describe_yaml_error;
+
+    #[test]
+    fn unknown_field_test() {
+        let source = indoc! {r#"
+            name: app
+            scrols:
+            - name: model
+              path: scrolls/model
+        "#};
+        let e = serde_yaml::from_str::<PackageConfig>(source).unwrap_err();
+        match describe_yaml_error("ggcode-info.yaml", source, &e) {
+            Error::Parse { path, message, location: Some(location), suggestion } => {
+                assert_eq!(path, "ggcode-info.yaml");
+                assert!(message.starts_with("unknown field `scrols`"));
+                assert!(!message.contains(" at line "));
+                assert_eq!((location.line, location.column), (1, 0));
+                assert_eq!(suggestion, Some("scrolls".to_string()));
+            }
+            e => panic!("Unexpected error: {}", e),
+        }
+
+        let source = "name: app\ntargets:\n- name: out\n  pth: out\n";
+        let e = serde_yaml::from_str::<PackageConfig>(source).unwrap_err();
+        let error = describe_yaml_error("ggcode-info.yaml", source, &e);
+        assert!(matches!(&error, Error::Parse { location: Some(location), .. } if (location.line, location.column) == (3, 2)));
+        assert!(error.to_string().ends_with("Did you mean path?"));
+    }
+
+    #[test]
+    fn action_arg_test() -> Result<(), serde_yaml::Error> {
+        let source = indoc! {r#"
+            name: app
+            actions:
+            - name: deploy
+              path: actions/deploy.luau
+              args:
+              - name: force
+                kind: Boolean
+              - name: replicas
+                kind: integer
+                short: r
+                default: 2
+                env: APP_REPLICAS
+              - name: stage
+                kind: enum
+                choices: [dev, prod]
+              - name: tags
+                multiple: true
+                default: [latest]
+              - name: direction
+                positional: true
+        "#};
+        let config = serde_yaml::from_str::<PackageConfig>(source)?;
+        let args = &config.actions[0].args;
+        assert_eq!(args[0].kind, Some(ActionArgKind::Boolean));
+        assert_eq!(args[1].kind, Some(ActionArgKind::Integer));
+        assert_eq!(args[1].short, Some('r'));
+        assert_eq!(args[1].default, Some(serde_yaml::Value::from(2)));
+        assert_eq!(args[1].env.as_deref(), Some("APP_REPLICAS"));
+        assert_eq!(args[2].choices, vec!["dev", "prod"]);
+        assert_eq!(args[3].multiple, Some(true));
+        assert_eq!(args[4].positional, Some(true));
+        Ok(())
+    }
+}
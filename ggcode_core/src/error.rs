@@ -0,0 +1,234 @@
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+use crate::config::Capability;
+
+/// What a failed Luau script was run for, so the failure can be worded after it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ScriptKind {
+    #[default]
+    Script,
+    Template,
+    Variables,
+    Action,
+}
+
+impl ScriptKind {
+    fn failure(&self) -> &'static str {
+        match self {
+            ScriptKind::Script => "Script failed",
+            ScriptKind::Template => "Cannot render template",
+            ScriptKind::Variables => "Cannot evaluate variables",
+            ScriptKind::Action => "Action failed",
+        }
+    }
+}
+
+/// Errors of the core crate. Messages are plain text; callers decide how to present them,
+/// see [`Error::describe`].
+#[derive(Debug)]
+pub enum Error {
+    /// No project configuration was found in the working directory
+    ConfigMissing { path: String },
+    ScrollNotFound { name: String },
+    ActionNotFound { name: String },
+    /// Actions depend on each other, `chain` ends with the action which closes the cycle
+    ActionCycle { chain: Vec<String> },
+    TargetNotFound { name: String },
+    TemplateNotFound { name: String },
+    InvalidUsage { message: String },
+    InvalidPath { path: String, reason: String },
+    InvalidFileMode { mode: String },
+    InvalidPattern { pattern: String, message: String },
+    InvalidVariables { scroll: String },
+    /// A template with a render extension is not valid UTF-8 text
+    InvalidTemplate { path: PathBuf },
+    /// A Tera template failed to parse or render, the location points at the failing expression
+    /// when it could be found in the source
+    Template {
+        name: String,
+        message: String,
+        location: Option<Box<SourceLocation>>,
+        suggestion: Option<String>,
+    },
+    /// A Luau script or template failed, the source is kept to point at the failing line
+    Script { kind: ScriptKind, name: String, source_code: String, message: String, cause: Box<mlua::Error> },
+    /// A configuration or variables file could not be parsed, the path is empty for sources
+    /// which were not read from a file
+    Parse {
+        path: String,
+        message: String,
+        location: Option<Box<SourceLocation>>,
+        suggestion: Option<String>,
+    },
+    Format { message: String },
+    File { path: PathBuf, operation: &'static str, cause: io::Error },
+    Command { command: String, message: String },
+    CommandFailed { command: String, code: Option<i32>, stderr: String },
+    CommandTimeout { command: String, seconds: f64 },
+    MissingCapability { capability: Capability, repository: String, operation: String },
+    WriteNotAllowed { operation: String },
+    /// A script asked a question in non-interactive mode without a default answer
+    PromptUnavailable { prompt: String },
+    Io(io::Error),
+    Yaml(serde_yaml::Error),
+    Lua(Box<mlua::Error>),
+}
+
+/// Source of a failing file with the zero-based position of the failure.
+#[derive(Debug, Clone)]
+pub struct SourceLocation {
+    pub source_code: String,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Error {
+    /// Builds the message, passing names, paths and commands through `highlight`.
+    pub fn describe<F: Fn(&str) -> String>(&self, highlight: F) -> String {
+        match self {
+            Error::ConfigMissing { path } => format!(
+                "Unable to resolve context. No valid configuration file was found in the working directory. \
+                Please check your {} file location.",
+                highlight(path)),
+            Error::ScrollNotFound { name } => format!("No scroll with name: {}", highlight(name)),
+            Error::ActionNotFound { name } => format!("No action with name: {}", highlight(name)),
+            Error::ActionCycle { chain } => format!(
+                "Cyclic dependency between actions: {}",
+                chain.iter().map(|name| highlight(name)).collect::<Vec<String>>().join(" -> ")),
+            Error::TargetNotFound { name } => format!("Invalid usage. Unknown target: {}", highlight(name)),
+            Error::TemplateNotFound { name } => format!("No template: {}", highlight(name)),
+            Error::InvalidUsage { message } => format!("Invalid usage. {}", message),
+            Error::InvalidPath { path, reason } => match path.is_empty() {
+                true => format!("Invalid path. {}", reason),
+                false => format!("Invalid path: {}. {}", highlight(path), reason),
+            },
+            Error::InvalidFileMode { mode } => format!(
+                "Invalid file mode: {}. Expected an octal value like 755.",
+                highlight(mode)),
+            Error::InvalidPattern { pattern, message } => format!("Invalid pattern: {}. {}", highlight(pattern), message),
+            Error::InvalidVariables { scroll } => format!(
+                "Cannot generate content using {} scroll. Invalid variables.",
+                highlight(scroll)),
+            Error::InvalidTemplate { path } => format!(
+                "Invalid template: {}. Files with the .tera or .luau extension should be valid UTF-8 text.",
+                highlight(&path.display().to_string())),
+            // Tera already names the failing template in its messages
+            Error::Template { message, suggestion, .. } => match suggestion {
+                Some(suggestion) => format!("{}. Did you mean {}?", message, highlight(suggestion)),
+                None => message.clone(),
+            },
+            Error::Script { kind, message, .. } => format!("{}: {}", kind.failure(), message),
+            Error::Parse { path, message, suggestion, .. } => match (path.is_empty(), suggestion) {
+                (true, _) => message.clone(),
+                (false, Some(suggestion)) => format!("Cannot parse: {}. {}. Did you mean {}?", highlight(path), message, highlight(suggestion)),
+                (false, None) => format!("Cannot parse: {}. {}", highlight(path), message),
+            },
+            Error::Format { message } => format!("Cannot serialize value. {}", message),
+            Error::File { path, operation, cause } => format!(
+                "Cannot {}: {}. {}",
+                operation,
+                highlight(&path.display().to_string()),
+                cause),
+            Error::Command { command, message } => format!("Cannot run command: {}. {}", highlight(command), message),
+            Error::CommandFailed { command, code, stderr } => format!(
+                "Command failed with exit code {}: {}\n{}",
+                code.map(|code| code.to_string()).unwrap_or("unknown".to_string()),
+                highlight(command),
+                stderr.trim_end()),
+            Error::CommandTimeout { command, seconds } => format!(
+                "Command timed out after {} seconds: {}",
+                seconds,
+                highlight(command)),
+            Error::MissingCapability { capability, repository, operation } => format!(
+                "Missing capability: {}. Repository {} is not allowed to use {}. \
+                Add the capability to the repository entry in ggcode-info.yaml to grant it.",
+                highlight(capability.name()),
+                highlight(repository),
+                operation),
+            Error::WriteNotAllowed { operation } => format!(
+                "Invalid operation: fs:{}. Writing files requires the fs-write capability and is only available in actions.",
+                operation),
+            Error::PromptUnavailable { prompt } => format!(
+                "Cannot ask: {}. Prompts are disabled in non-interactive mode and no default answer was given.",
+                highlight(prompt)),
+            Error::Io(e) => e.to_string(),
+            Error::Yaml(e) => e.to_string(),
+            Error::Lua(e) => e.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.describe(|s| s.to_string()))
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Script { cause, .. } => Some(cause.as_ref()),
+            Error::File { cause, .. } => Some(cause),
+            Error::Io(e) => Some(e),
+            Error::Yaml(e) => Some(e),
+            Error::Lua(e) => Some(e.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+impl From<serde_yaml::Error> for Error {
+    fn from(e: serde_yaml::Error) -> Self {
+        Error::Yaml(e)
+    }
+}
+
+impl From<mlua::Error> for Error {
+    fn from(e: mlua::Error) -> Self {
+        Error::Lua(Box::new(e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::{Error, ScriptKind};
+    use crate::renderer::luau_extras::LuauLimits;
+    use crate::storage::{parse_file_mode, resolve_inner_path};
+    use crate::utils::formats::{parse_dotenv, stringify_toml};
+
+    #[test]
+    fn error_variants_test() {
+        let error = parse_file_mode("999").unwrap_err();
+        assert!(matches!(&error, Error::InvalidFileMode { mode } if mode == "999"));
+        assert_eq!(error.to_string(), "Invalid file mode: 999. Expected an octal value like 755.");
+        assert_eq!(
+            error.describe(|s| format!("<{}>", s)),
+            "Invalid file mode: <999>. Expected an octal value like 755.");
+
+        let error = resolve_inner_path(&"../outside".to_string()).unwrap_err();
+        assert!(matches!(&error, Error::InvalidPath { path, .. } if path == "../outside"));
+
+        let error = resolve_inner_path(&"".to_string()).unwrap_err();
+        assert_eq!(error.to_string(), "Invalid path. Path should not be empty.");
+
+        let error = LuauLimits::default().script_error(
+            ScriptKind::Action, "deploy.luau", "error('boom')", mlua::Error::RuntimeError("deploy.luau:1: boom".to_string()));
+        assert_eq!(error.to_string(), "Action failed: deploy.luau:1: boom");
+
+        let error = parse_dotenv("NAME\n").unwrap_err();
+        assert!(matches!(&error, Error::Parse { path, .. } if path.is_empty()));
+        assert_eq!(error.to_string(), "Line 1. Expected KEY=value.");
+
+        let error = stringify_toml(&serde_yaml::Value::from("app")).unwrap_err();
+        assert!(matches!(&error, Error::Format { .. }));
+        assert_eq!(error.to_string(), "Cannot serialize value. Only tables can be represented as TOML documents.");
+    }
+}
@@ -1,15 +1,27 @@
+use std::{cmp, panic, thread};
+use std::any::Any;
+use std::collections::BTreeMap;
 use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
+use std::panic::AssertUnwindSafe;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{channel, Receiver};
 
-use console::style;
-use relative_path::RelativePathBuf;
+use glob::Pattern;
+use relative_path::{RelativePath, RelativePathBuf};
 use serde_yaml::Value;
 
+use crate::capability::resolve_sandbox;
+use crate::config::DEFAULT_IGNORE_NAME;
+use crate::error::Error;
 use crate::generator::GeneratorEvent::{Finish, Start};
 use crate::renderer::builder::RendererBuilder;
+use crate::renderer::luau_renderer::LuaRenderer;
+use crate::renderer::tera_renderer::TeraRenderer;
+use crate::renderer::luau_extras::{LuauFs, LuauLimits};
 use crate::ResolvedContext;
-use crate::scroll::find_scroll_by_full_name;
-use crate::storage::{load_templates, load_variables, resolve_inner_path, resolve_search_locations, save_target_file};
+use crate::scroll::{find_scroll_by_full_name, ScrollRef};
+use crate::storage::{load_file_mode, load_library_templates, load_template_string, load_templates, load_variables, parse_file_mode, resolve_inner_path, resolve_search_locations, save_target_file, save_target_stream};
 use crate::types::AppResult;
 use crate::utils::merge_yaml;
 
@@ -27,7 +39,14 @@ pub trait GeneratorObserver {
 pub enum GeneratorEvent {
     Start(String),
     Message(String),
-    Finish(String)
+    /// A line printed by a running command, without the line break
+    Output(String),
+    /// An operation completed successfully
+    Finish(String),
+    /// An operation was only reported, because of a dry run
+    Skip(String),
+    /// An operation failed, the error itself is still returned to the caller
+    Failure(String),
 }
 
 impl DefaultGenerator {
@@ -42,104 +61,350 @@ impl DefaultGenerator {
         }
     }
 
-    pub fn generate(&self, scroll_name: &String, target_path: &PathBuf, dry_run: bool, overrides: Option<Value>) -> AppResult<()> {
+    /// Loads the variables of a scroll. Luau variables can read from the project directory and,
+    /// when given, from the target directory.
+    pub fn load_scroll_variables(&self, scroll_name: &String, target_path: Option<&PathBuf>) -> AppResult<Value> {
         let scroll = find_scroll_by_full_name(&self.context, scroll_name)?;
+        self.load_variables(&scroll, target_path)
+    }
 
-        let path = match scroll.dependency_name {
-            None => resolve_inner_path(&scroll.scroll.path)?,
-            Some(n) => RelativePathBuf::from("ggcode_modules").join(&n).join(&scroll.scroll.path)
-        };
+    /// Renders a single template of a scroll without writing it. Templates without a render
+    /// extension are returned as they are.
+    pub fn render_template(&self, scroll_name: &String, template_name: &String, target_path: Option<&PathBuf>, overrides: Option<Value>) -> AppResult<String> {
+        let scroll = find_scroll_by_full_name(&self.context, scroll_name)?;
+        let (builder, templates) = self.prepare(&scroll, target_path, overrides)?;
+
+        let template_path = templates
+            .get(template_name)
+            .ok_or_else(|| Error::TemplateNotFound { name: template_name.clone() })?;
+
+        match RelativePath::new(template_name).extension() {
+            Some("tera") => builder.build_tera()?.render(template_name),
+            Some("luau") => builder.build_lua()?.render(template_name),
+            _ => load_template_string(template_path),
+        }
+    }
+
+    /// Generates the files of a scroll into the target directory and returns the paths of
+    /// the written files. Nothing is written in a dry run.
+    pub fn generate(&self, scroll_name: &String, target_path: &PathBuf, dry_run: bool, overrides: Option<Value>) -> AppResult<Vec<PathBuf>> {
+        let scroll = find_scroll_by_full_name(&self.context, scroll_name)?;
+
+        let (builder, templates) = self.prepare(&scroll, Some(target_path), overrides)?;
+
+        let mut modes: Vec<(Pattern, u32)> = vec![];
+
+        for (pattern, mode) in &scroll.scroll.modes {
+            let compiled = Pattern::new(pattern)
+                .map_err(|e| Error::InvalidPattern { pattern: pattern.clone(), message: e.to_string() })?;
+            modes.push((compiled, parse_file_mode(mode)?));
+        }
+
+        let tera = &builder.build_tera()?;
+        let lua = &builder.build_lua()?;
+        let noop = &builder.build_noop()?;
+
+        // Output paths are resolved upfront, so templates can be rendered in parallel
+        let mut jobs: Vec<(&String, &PathBuf, String)> = vec![];
+        for (key, template_path) in &templates {
+            if is_partial_template(key) {
+                continue;
+            }
+            jobs.push((key, template_path, lua.eval_string_template(format!("`{}`", key))?));
+        }
+
+        let mut written: Vec<PathBuf> = vec![];
 
-        // println!("Scroll name {} -> {} -> {}", scroll_name, &target_path.as_path().to_str().unwrap().to_string(), path.to_string());
+        // Files are written in the order of the jobs, while the next ones are still rendering
+        render_in_parallel(&builder, tera, &jobs, |rendered| {
+            for (index, &(key, template_path, ref file_path)) in jobs.iter().enumerate() {
+                let message = format!("Rendering {} template...", key);
+                self.notify(Start(message));
+                let file_relative_path = RelativePathBuf::from(file_path);
 
-        let values_directory_path = path.join("variables");
+                let file_content = rendered.take(index)?;
+                let target_file_relative_path = match file_content {
+                    Some(_) => file_relative_path.with_extension("".to_string()),
+                    // Files without a render extension are streamed to the target as is
+                    None => file_relative_path.clone(),
+                };
+
+                let save = |relative_path: &RelativePathBuf, overwrite: bool, mode: Option<u32>| -> AppResult<()> {
+                    match &file_content {
+                        Some(content) => save_target_file(target_path, relative_path, content, overwrite, mode),
+                        None => save_target_stream(target_path, relative_path, &mut noop.open(key)?, overwrite, mode),
+                    }
+                };
+
+                match dry_run {
+                    true => {
+                        if file_content.is_none() {
+                            noop.open(key)?;
+                        }
+                        self.notify(Finish(format!("Rendered template: {}", &file_path)));
+                    }
+                    false => {
+                        let file_path = target_file_relative_path.to_path(&target_path);
+                        self.notify(Finish(format!("Generated file: {}", file_path.to_str().unwrap())));
+                        let file_name = file_path.file_name().unwrap().to_str().unwrap();
+
+                        let file_mode = modes
+                            .iter()
+                            .find(|(pattern, _)| pattern.matches(target_file_relative_path.as_str()))
+                            .map(|(_, mode)| *mode)
+                            .or_else(|| load_file_mode(template_path));
+
+                        if file_name.starts_with("!") {
+                            // ignore
+                        } else if file_name.starts_with("+") {
+                            let alternate_name = file_name.strip_prefix("+").unwrap().to_string();
+                            let alternate_path = target_file_relative_path.with_file_name(alternate_name);
+                            // Existing files are kept
+                            if !alternate_path.to_path(target_path).exists() {
+                                save(&alternate_path, false, file_mode)?;
+                                written.push(alternate_path.to_path(target_path));
+                            }
+                        } else {
+                            save(&target_file_relative_path, true, file_mode)?;
+                            written.push(file_path);
+                        }
+                    }
+                }
+            }
+            Ok(())
+        })?;
+
+        Ok(written)
+    }
+
+    fn load_variables(&self, scroll: &ScrollRef, target_path: Option<&PathBuf>) -> AppResult<Value> {
+        let values_directory_path = scroll_path(scroll)?.join("variables");
         let search_locations = resolve_search_locations(&self.context.current_config);
 
-        let mut variables = load_variables(&values_directory_path, &search_locations)?;
+        let mut fs = LuauFs::new(&self.context)?;
+        if let Some(target_path) = target_path {
+            fs = fs.with_root(target_path);
+        }
+
+        let sandbox = resolve_sandbox(&self.context, scroll.dependency_name.as_ref());
+
+        let limits = LuauLimits::configured(self.context.current_config.limits.as_ref());
+
+        load_variables(&values_directory_path, &search_locations, &fs, &sandbox, limits)
+    }
+
+    /// Prepares the renderer with the variables and templates of a scroll.
+    fn prepare(&self, scroll: &ScrollRef, target_path: Option<&PathBuf>, overrides: Option<Value>) -> AppResult<(RendererBuilder, BTreeMap<String, PathBuf>)> {
+        let path = scroll_path(scroll)?;
+
+        let mut variables = self.load_variables(scroll, target_path)?;
 
         if let Some(o) = overrides {
             merge_yaml(&mut variables, o);
         }
 
-        let mut builder = RendererBuilder::new();
+        let sandbox = resolve_sandbox(&self.context, scroll.dependency_name.as_ref());
+        let mut builder = RendererBuilder::new()
+            .with_sandbox(sandbox)
+            .with_luau_limits(LuauLimits::configured(self.context.current_config.limits.as_ref()));
 
         let variables_mapping = match variables.as_mapping() {
             Some(m) => m,
-            None => return Err(format!("Cannot generate content using {} scroll. Invalid variables.", style(scroll_name).yellow()).into())
+            None => return Err(Error::InvalidVariables { scroll: scroll.full_name.clone() })
         };
 
         for (key, value) in variables_mapping {
             builder = builder.with_value(key.as_str().unwrap().to_string(), value);
         }
 
-        // if let Some(path) = &overrides_path {
-        //     if path.is_directory() {
-        //         load_variables(path)
-        //     }
-        // }
-
         let templates_directory_path = path.join("templates");
-        let templates = load_templates(&templates_directory_path);
+        let templates = load_templates(&templates_directory_path, &path.join(DEFAULT_IGNORE_NAME))?;
 
         for (key, value) in &templates {
             builder = builder.with_file_template(key, value);
         }
 
-        let tera = &builder.build_tera()?;
-        let lua = &builder.build_lua()?;
-        let noop = &builder.build_noop()?;
+        for (key, value) in load_library_templates(&self.context.current_config)? {
+            builder = builder.with_library_template(key, value);
+        }
 
-        for (key, _value) in &templates {
-            let message = format!("Rendering {} template...", style(key).yellow());
-            self.notify(Start(message));
-            let file_path = lua.eval_string_template(format!("`{}`", key))?;
-            let file_relative_path = RelativePathBuf::from(&file_path);
-
-            let (target_file_relative_path, file_content) = match &file_relative_path.extension() {
-                Some("tera") => (
-                    file_relative_path.with_extension("".to_string()),
-                    tera.render(key)?
-                ),
-                Some("luau") => (
-                    file_relative_path.with_extension("".to_string()),
-                    lua.render(key)?
-                ),
-                _ => (
-                    file_relative_path.clone(),
-                    noop.render(key)?
-                )
-            };
-
-            match dry_run {
-                true => {
-                    let message = format!(
-                        "{} Rendered template: {}",
-                        style("[DONE]").green(),
-                        &file_path);
-                    self.notify(Finish(message.to_string()));
+        Ok((builder, templates))
+    }
+}
+
+/// Directory of a scroll, relative to the project directory.
+fn scroll_path(scroll: &ScrollRef) -> AppResult<RelativePathBuf> {
+    match &scroll.dependency_name {
+        None => resolve_inner_path(&scroll.scroll.path),
+        Some(n) => Ok(RelativePathBuf::from("ggcode_modules").join(n).join(&scroll.scroll.path)),
+    }
+}
+
+/// Results of the templates rendered by [`render_in_parallel`], taken in the order of the jobs.
+struct RenderedJobs {
+    names: Vec<String>,
+    receiver: Receiver<(usize, AppResult<Option<String>>)>,
+    pending: BTreeMap<usize, AppResult<Option<String>>>,
+}
+
+impl RenderedJobs {
+    /// Waits for the rendered content of a job, templates without a render extension have none.
+    fn take(&mut self, index: usize) -> AppResult<Option<String>> {
+        loop {
+            if let Some(result) = self.pending.remove(&index) {
+                return result;
+            }
+            match self.receiver.recv() {
+                Ok((received, result)) => {
+                    self.pending.insert(received, result);
                 }
-                false => {
-                    let file_path = target_file_relative_path.to_path(&target_path);
-                    let message = format!(
-                        "{} Generated file: {}",
-                        style("[DONE]").green(),
-                        file_path.to_str().unwrap().to_string());
-                    self.notify(Finish(message));
-                    let file_name = file_path.file_name().unwrap().to_str().unwrap();
-
-                    if file_name.starts_with("!") {
-                        // ignore
-                    } else if file_name.starts_with("+") {
-                        let alternate_name = file_name.strip_prefix("+").unwrap().to_string();
-                        let alternate_path = target_file_relative_path.with_file_name(alternate_name);
-                        save_target_file(&target_path, &alternate_path, &file_content, false)?;
-                    } else {
-                        save_target_file(&target_path, &target_file_relative_path, &file_content, true)?;
+                Err(_) => return Err(Error::Template {
+                    name: self.names[index].clone(),
+                    message: format!("Template {} was not rendered.", self.names[index]),
+                    location: None,
+                    suggestion: None,
+                }),
+            }
+        }
+    }
+}
+
+/// Renders `.tera` and `.luau` templates across a pool of threads, while `consume` takes the
+/// results on the calling thread. Tera is shared between threads, while every thread prepares
+/// its own Luau state. A panic while rendering is returned as the error of its template.
+fn render_in_parallel<F>(builder: &RendererBuilder, tera: &TeraRenderer, jobs: &[(&String, &PathBuf, String)], consume: F) -> AppResult<()>
+where
+    F: FnOnce(&mut RenderedJobs) -> AppResult<()>,
+{
+    let workers = thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(1)
+        .clamp(1, cmp::max(jobs.len(), 1));
+    let next = AtomicUsize::new(0);
+    let (sender, receiver) = channel();
+
+    thread::scope(|scope| {
+        for _ in 0..workers {
+            let sender = sender.clone();
+            let next = &next;
+            scope.spawn(move || {
+                let mut lua: Option<LuaRenderer> = None;
+                loop {
+                    let index = next.fetch_add(1, Ordering::Relaxed);
+                    let Some((key, _, file_path)) = jobs.get(index) else {
+                        break;
+                    };
+                    let result = panic::catch_unwind(AssertUnwindSafe(|| render_job(builder, tera, &mut lua, key, file_path)))
+                        .unwrap_or_else(|payload| {
+                            // The Luau state may be left inconsistent
+                            lua = None;
+                            Err(Error::Template {
+                                name: key.to_string(),
+                                message: format!("Rendering {} template panicked: {}", key, panic_message(&payload)),
+                                location: None,
+                                suggestion: None,
+                            })
+                        });
+                    if sender.send((index, result)).is_err() {
+                        break;
                     }
                 }
+            });
+        }
+        drop(sender);
+
+        let names = jobs.iter().map(|(key, _, _)| key.to_string()).collect();
+        let mut rendered = RenderedJobs { names, receiver, pending: BTreeMap::new() };
+        let result = consume(&mut rendered);
+        // Remaining jobs are not needed after a failure
+        next.store(jobs.len(), Ordering::Relaxed);
+        drop(rendered);
+        result
+    })
+}
+
+fn render_job(builder: &RendererBuilder, tera: &TeraRenderer, lua: &mut Option<LuaRenderer>, key: &String, file_path: &str) -> AppResult<Option<String>> {
+    match RelativePath::new(file_path).extension() {
+        Some("tera") => tera.render(key).map(Some),
+        Some("luau") => {
+            if lua.is_none() {
+                *lua = Some(builder.build_lua()?);
             }
+            lua.as_ref().unwrap().render(key).map(Some)
+        }
+        _ => Ok(None),
+    }
+}
+
+fn panic_message(payload: &Box<dyn Any + Send>) -> String {
+    payload.downcast_ref::<&str>().map(|s| s.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "unknown cause".to_string())
+}
+
+/// Partials are `.tera` templates with a file name prefixed by `_`, e.g. `_layout.html.tera`.
+/// They can be extended, included or imported by other templates, but never produce output files.
+pub fn is_partial_template(name: &str) -> bool {
+    let path = RelativePathBuf::from(name);
+    match (path.file_name(), path.extension()) {
+        (Some(file_name), Some("tera")) => file_name.starts_with('_'),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use crate::generator::{is_partial_template, render_in_parallel};
+    use crate::renderer::builder::RendererBuilder;
+    use crate::types::AppResult;
+
+    #[test]
+    fn render_in_parallel_test() -> AppResult<()> {
+        let mut builder = RendererBuilder::new().with_value("name", "item");
+        let mut names: Vec<String> = vec![];
+        for i in 0..40 {
+            let tera_name = format!("{:02}.txt.tera", i);
+            let luau_name = format!("{:02}.txt.luau", i);
+            builder = builder
+                .with_raw_template(&tera_name, format!("{{{{ name }}}} {}", i))
+                .with_raw_template(&luau_name, format!("template:print(`{{name}} {}`)", i));
+            names.push(tera_name);
+            names.push(luau_name);
         }
+        names.push("logo.png".to_string());
+
+        let tera = builder.build_tera()?;
+        let template_path = PathBuf::new();
+        let jobs: Vec<(&String, &PathBuf, String)> = names
+            .iter()
+            .map(|name| (name, &template_path, name.clone()))
+            .collect();
+
+        let mut taken = 0;
+        render_in_parallel(&builder, &tera, &jobs, |rendered| {
+            for i in 0..jobs.len() {
+                match i {
+                    80 => assert_eq!(rendered.take(i)?, None),
+                    _ => assert_eq!(rendered.take(i)?, Some(format!("item {}", i / 2))),
+                }
+                taken += 1;
+            }
+            Ok(())
+        })?;
+        assert_eq!(taken, 81);
 
         Ok(())
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn is_partial_template_test() {
+        assert!(is_partial_template("_macros.tera"));
+        assert!(is_partial_template("layouts/_base.html.tera"));
+        assert!(!is_partial_template("_config.yml"));
+        assert!(!is_partial_template("_build.luau"));
+        assert!(!is_partial_template("docs_/index.html.tera"));
+        assert!(!is_partial_template("README.md.tera"));
+    }
+}
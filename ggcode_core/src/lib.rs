@@ -1,15 +1,18 @@
-use std::error::Error;
 use std::path::PathBuf;
 
 use config::PackageConfig;
+pub use error::Error;
+use types::AppResult;
 
 pub mod config;
+pub mod error;
 pub mod scroll;
 pub mod storage;
 pub mod utils;
 pub mod types;
 pub mod renderer;
 pub mod action;
+pub mod capability;
 pub mod generator;
 mod luau;
 
@@ -30,7 +33,7 @@ pub struct ResolvedContext {
 }
 
 impl Context {
-    pub fn resolve(&self) -> Result<ResolvedContext, Box<dyn Error>> {
+    pub fn resolve(&self) -> AppResult<ResolvedContext> {
         match &self.current_config {
             Some(config) => Ok(
                 ResolvedContext {
@@ -40,11 +43,7 @@ impl Context {
                     current_config: config.clone()
                 }
             ),
-            None => {
-                return Err(
-                    "Unable to resolve context. No valid configuration file was found in the working directory. \
-                    Please check your ggcode-info.yaml file location.".into())
-            }
+            None => Err(Error::ConfigMissing { path: self.config_path.clone() }),
         }
     }
 }
@@ -0,0 +1,16 @@
+use mlua::{AnyUserData, UserData, UserDataMethods};
+
+use crate::utils::strings::{CASES, to_case};
+
+#[derive(Debug, Copy, Clone)]
+pub struct LuauCase;
+
+impl UserData for LuauCase {
+    fn add_methods<'lua, M: UserDataMethods<'lua, Self>>(methods: &mut M) {
+        for (name, case) in CASES {
+            methods.add_function(name, move |_, (_ud, value): (AnyUserData, String)| {
+                Ok(to_case(&value, case))
+            });
+        }
+    }
+}
@@ -0,0 +1,23 @@
+use std::collections::BTreeMap;
+use std::env;
+
+use mlua::{AnyUserData, LuaSerdeExt, UserData, UserDataMethods};
+
+/// Read access to the environment variables of the process.
+#[derive(Debug, Copy, Clone)]
+pub struct LuauEnv;
+
+impl UserData for LuauEnv {
+    fn add_methods<'lua, M: UserDataMethods<'lua, Self>>(methods: &mut M) {
+        methods.add_function("get", |_, (_ud, name): (AnyUserData, String)| {
+            Ok(env::var(name).ok())
+        });
+
+        methods.add_function("vars", |lua, _ud: AnyUserData| {
+            let vars: BTreeMap<String, String> = env::vars_os()
+                .map(|(key, value)| (key.to_string_lossy().to_string(), value.to_string_lossy().to_string()))
+                .collect();
+            lua.to_value(&vars)
+        });
+    }
+}
@@ -0,0 +1,27 @@
+use mlua::{AnyUserData, LuaSerdeExt, UserData, UserDataMethods};
+use mlua::Error::RuntimeError;
+use serde_yaml::Value;
+
+use crate::utils::formats::{parse_ini, stringify_ini};
+
+#[derive(Debug, Copy, Clone)]
+pub struct LuauIni;
+
+impl UserData for LuauIni {
+    fn add_methods<'lua, M: UserDataMethods<'lua, Self>>(methods: &mut M) {
+        methods.add_function("stringify", |lua, (_ud, value): (AnyUserData, mlua::Value)| {
+            let result: mlua::Result<Value> = lua.from_value(value);
+            match result {
+                Ok(yaml) => stringify_ini(&yaml).map_err(|e| RuntimeError(e.to_string())),
+                Err(e) => Err(RuntimeError(format!("Cannot serialize value. {}", e))),
+            }
+        });
+
+        methods.add_function("parse", |lua, (_ud, source): (AnyUserData, String)| {
+            match parse_ini(&source) {
+                Ok(yaml) => lua.to_value(&yaml),
+                Err(e) => Err(RuntimeError(format!("Cannot parse INI. {}", e))),
+            }
+        });
+    }
+}
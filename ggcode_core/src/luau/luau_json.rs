@@ -1,27 +1,54 @@
 use mlua::{AnyUserData, LuaSerdeExt, UserData, UserDataMethods};
 use mlua::Error::RuntimeError;
+use serde::Deserialize;
 use serde_json::Value;
 
+use crate::error::Error;
 use crate::types::AppResult;
 
+#[derive(Debug, Default, Deserialize)]
+struct StringifyOptions {
+    pretty: Option<bool>,
+}
+
 #[derive(Debug, Copy, Clone)]
 pub struct LuauJson;
 
 impl LuauJson {
-    fn stringify(value: &Value) -> AppResult<String> {
-        let output = serde_json::to_string_pretty(value)?;
-        return Ok(output)
+    fn stringify(value: &Value, pretty: bool) -> AppResult<String> {
+        let output = match pretty {
+            true => serde_json::to_string_pretty(value),
+            false => serde_json::to_string(value),
+        };
+        output.map_err(|e| Error::Format { message: e.to_string() })
+    }
+
+    fn parse(source: &str) -> AppResult<Value> {
+        serde_json::from_str(source)
+            .map_err(|e| Error::Parse { path: String::new(), message: e.to_string(), location: None, suggestion: None })
     }
 }
 
 impl UserData for LuauJson {
     fn add_methods<'lua, M: UserDataMethods<'lua, Self>>(methods: &mut M) {
-        methods.add_function("stringify", |lua, (_ud, value): (AnyUserData, mlua::Value)| {
+        methods.add_function("stringify", |lua, (_ud, value, options): (AnyUserData, mlua::Value, mlua::Value)| {
+            let options: StringifyOptions = match options {
+                mlua::Value::Nil => StringifyOptions::default(),
+                options => lua.from_value(options)
+                    .map_err(|e| RuntimeError(format!("Invalid stringify options. {}", e)))?,
+            };
             let result = lua.from_value(value);
             match result {
-                Ok(yaml) => Self::stringify(&yaml).or_else(|e| Err(RuntimeError(format!("Cannot serialize value. {}", e).to_string()))),
+                Ok(json) => Self::stringify(&json, options.pretty.unwrap_or(true)).map_err(|e| RuntimeError(format!("Cannot serialize value. {}", e))),
                 Err(e) => return Err(RuntimeError(format!("Cannot serialize value. {}", e).to_string())),
             }
-        })
+        });
+
+        methods.add_function("parse", |lua, (_ud, source): (AnyUserData, String)| {
+            match Self::parse(&source) {
+                Ok(json) => lua.to_value(&json),
+                Err(e) => Err(RuntimeError(format!("Cannot parse JSON. {}", e))),
+            }
+        });
     }
 }
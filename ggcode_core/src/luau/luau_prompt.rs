@@ -0,0 +1,199 @@
+use std::sync::Arc;
+
+use mlua::{AnyUserData, LuaSerdeExt, UserData, UserDataMethods};
+use mlua::Error::RuntimeError;
+
+use crate::error::Error;
+use crate::types::AppResult;
+
+/// Asks the user the questions of scripts, implemented by the front end which owns the terminal.
+/// Items and defaults are checked before a question is asked.
+pub trait Prompter {
+    fn input(&self, prompt: &str, default: Option<String>) -> AppResult<String>;
+
+    fn confirm(&self, prompt: &str, default: Option<bool>) -> AppResult<bool>;
+
+    /// Returns the position of the selected item, `default` is the position selected at first.
+    fn select(&self, prompt: &str, items: &[String], default: usize) -> AppResult<usize>;
+
+    /// Returns the positions of the checked items, `checked` tells which are checked at first.
+    fn multiselect(&self, prompt: &str, items: &[String], checked: &[bool]) -> AppResult<Vec<usize>>;
+
+    /// Reads a secret without echoing it.
+    fn password(&self, prompt: &str) -> AppResult<String>;
+}
+
+/// Questions asked by actions. Without a prompter the defaults are used as answers,
+/// while questions without a default fail.
+#[derive(Clone, Default)]
+pub struct LuauPrompt {
+    pub prompter: Option<Arc<dyn Prompter>>,
+}
+
+impl LuauPrompt {
+    pub fn new(prompter: Option<Arc<dyn Prompter>>) -> LuauPrompt {
+        LuauPrompt { prompter }
+    }
+
+    pub fn input(&self, prompt: &str, default: Option<String>) -> AppResult<String> {
+        match &self.prompter {
+            Some(prompter) => prompter.input(prompt, default),
+            None => default.ok_or_else(|| unanswered(prompt)),
+        }
+    }
+
+    pub fn confirm(&self, prompt: &str, default: Option<bool>) -> AppResult<bool> {
+        match &self.prompter {
+            Some(prompter) => prompter.confirm(prompt, default),
+            None => default.ok_or_else(|| unanswered(prompt)),
+        }
+    }
+
+    pub fn select(&self, prompt: &str, items: &[String], default: Option<String>) -> AppResult<String> {
+        if items.is_empty() {
+            return Err(Error::InvalidUsage { message: format!("Nothing to select for: {}", prompt) });
+        }
+        let position = default
+            .map(|default| position_of(items, &default))
+            .transpose()?;
+        let selected = match &self.prompter {
+            Some(prompter) => prompter.select(prompt, items, position.unwrap_or(0))?,
+            None => position.ok_or_else(|| unanswered(prompt))?,
+        };
+        items.get(selected)
+            .cloned()
+            .ok_or_else(|| Error::InvalidUsage { message: format!("Nothing selected for: {}", prompt) })
+    }
+
+    pub fn multiselect(&self, prompt: &str, items: &[String], defaults: Option<Vec<String>>) -> AppResult<Vec<String>> {
+        let positions = defaults
+            .map(|defaults| defaults.iter().map(|default| position_of(items, default)).collect::<AppResult<Vec<usize>>>())
+            .transpose()?;
+        let selected = match &self.prompter {
+            Some(prompter) => {
+                let checked: Vec<bool> = (0..items.len())
+                    .map(|i| positions.as_ref().is_some_and(|positions| positions.contains(&i)))
+                    .collect();
+                prompter.multiselect(prompt, items, &checked)?
+            }
+            None => positions.ok_or_else(|| unanswered(prompt))?,
+        };
+        Ok(selected.into_iter().filter_map(|i| items.get(i).cloned()).collect())
+    }
+
+    /// Reads a secret; there is no default, so it always fails without a prompter.
+    pub fn password(&self, prompt: &str) -> AppResult<String> {
+        match &self.prompter {
+            Some(prompter) => prompter.password(prompt),
+            None => Err(unanswered(prompt)),
+        }
+    }
+}
+
+fn position_of(items: &[String], value: &String) -> AppResult<usize> {
+    items.iter()
+        .position(|item| item.eq(value))
+        .ok_or_else(|| Error::InvalidUsage { message: format!("Default {} is not one of the items.", value) })
+}
+
+fn unanswered(prompt: &str) -> Error {
+    Error::PromptUnavailable { prompt: prompt.to_string() }
+}
+
+impl UserData for LuauPrompt {
+    fn add_methods<'lua, M: UserDataMethods<'lua, Self>>(methods: &mut M) {
+        methods.add_function("input", |_, (ud, prompt, default): (AnyUserData, String, Option<String>)| {
+            ud.borrow::<LuauPrompt>()?.input(&prompt, default).map_err(|e| RuntimeError(e.to_string()))
+        });
+
+        methods.add_function("confirm", |_, (ud, prompt, default): (AnyUserData, String, Option<bool>)| {
+            ud.borrow::<LuauPrompt>()?.confirm(&prompt, default).map_err(|e| RuntimeError(e.to_string()))
+        });
+
+        methods.add_function("select", |lua, (ud, prompt, items, default): (AnyUserData, String, mlua::Value, Option<String>)| {
+            let items: Vec<String> = lua.from_value(items)?;
+            ud.borrow::<LuauPrompt>()?.select(&prompt, &items, default).map_err(|e| RuntimeError(e.to_string()))
+        });
+
+        methods.add_function("multiselect", |lua, (ud, prompt, items, defaults): (AnyUserData, String, mlua::Value, mlua::Value)| {
+            let items: Vec<String> = lua.from_value(items)?;
+            let defaults: Option<Vec<String>> = lua.from_value(defaults)?;
+            let selected = ud.borrow::<LuauPrompt>()?.multiselect(&prompt, &items, defaults)
+                .map_err(|e| RuntimeError(e.to_string()))?;
+            lua.to_value(&selected)
+        });
+
+        methods.add_function("password", |_, (ud, prompt): (AnyUserData, String)| {
+            ud.borrow::<LuauPrompt>()?.password(&prompt).map_err(|e| RuntimeError(e.to_string()))
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::error::Error;
+    use crate::luau::luau_prompt::{LuauPrompt, Prompter};
+    use crate::types::AppResult;
+
+    /// Answers with the defaults it was given, or the last item.
+    struct DefaultPrompter;
+
+    impl Prompter for DefaultPrompter {
+        fn input(&self, _: &str, default: Option<String>) -> AppResult<String> {
+            Ok(default.unwrap_or("typed".to_string()))
+        }
+
+        fn confirm(&self, _: &str, default: Option<bool>) -> AppResult<bool> {
+            Ok(default.unwrap_or(true))
+        }
+
+        fn select(&self, _: &str, items: &[String], default: usize) -> AppResult<usize> {
+            Ok(if default == 0 { items.len() - 1 } else { default })
+        }
+
+        fn multiselect(&self, _: &str, _: &[String], checked: &[bool]) -> AppResult<Vec<usize>> {
+            Ok((0..checked.len()).filter(|i| !checked[*i]).collect())
+        }
+
+        fn password(&self, _: &str) -> AppResult<String> {
+            Ok("secret".to_string())
+        }
+    }
+
+    #[test]
+    fn non_interactive_prompt_test() -> AppResult<()> {
+        let prompt = LuauPrompt::default();
+        let items = vec!["dev".to_string(), "prod".to_string()];
+
+        assert_eq!(prompt.input("Name", Some("app".to_string()))?, "app");
+        assert!(!prompt.confirm("Overwrite?", Some(false))?);
+        assert_eq!(prompt.select("Stage", &items, Some("prod".to_string()))?, "prod");
+        assert_eq!(prompt.multiselect("Stages", &items, Some(vec![]))?, Vec::<String>::new());
+
+        let error = prompt.input("Name", None).unwrap_err();
+        assert!(matches!(&error, Error::PromptUnavailable { prompt } if prompt == "Name"));
+        assert!(prompt.password("Token").is_err());
+        assert!(matches!(prompt.select("Stage", &items, Some("qa".to_string())), Err(Error::InvalidUsage { .. })));
+        Ok(())
+    }
+
+    #[test]
+    fn prompter_test() -> AppResult<()> {
+        let prompt = LuauPrompt::new(Some(Arc::new(DefaultPrompter)));
+        let items = vec!["dev".to_string(), "qa".to_string(), "prod".to_string()];
+
+        assert_eq!(prompt.input("Name", None)?, "typed");
+        assert!(prompt.confirm("Overwrite?", None)?);
+        assert_eq!(prompt.select("Stage", &items, None)?, "prod");
+        assert_eq!(prompt.select("Stage", &items, Some("qa".to_string()))?, "qa");
+        assert_eq!(prompt.multiselect("Stages", &items, Some(vec!["qa".to_string()]))?, vec!["dev", "prod"]);
+        assert_eq!(prompt.password("Token")?, "secret");
+
+        // Defaults are checked before the question is asked
+        assert!(matches!(prompt.select("Stage", &items, Some("test".to_string())), Err(Error::InvalidUsage { .. })));
+        assert!(matches!(prompt.select("Stage", &[], None), Err(Error::InvalidUsage { .. })));
+        Ok(())
+    }
+}
@@ -0,0 +1,45 @@
+use mlua::{AnyUserData, LuaSerdeExt, UserData, UserDataMethods};
+use mlua::Error::RuntimeError;
+use serde::Deserialize;
+
+use crate::utils::strings::{DEFAULT_INDENT_PREFIX, DEFAULT_WRAP_WIDTH, dedent, indent, plural, singular, wrap};
+
+#[derive(Debug, Default, Deserialize)]
+struct IndentOptions {
+    prefix: Option<String>,
+    first: Option<bool>,
+    blank: Option<bool>,
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct LuauStr;
+
+impl UserData for LuauStr {
+    fn add_methods<'lua, M: UserDataMethods<'lua, Self>>(methods: &mut M) {
+        methods.add_function("plural", |_, (_ud, value): (AnyUserData, String)| {
+            Ok(plural(&value))
+        });
+
+        methods.add_function("singular", |_, (_ud, value): (AnyUserData, String)| {
+            Ok(singular(&value))
+        });
+
+        methods.add_function("indent", |lua, (_ud, value, options): (AnyUserData, String, mlua::Value)| {
+            let options: IndentOptions = match options {
+                mlua::Value::Nil => IndentOptions::default(),
+                options => lua.from_value(options)
+                    .map_err(|e| RuntimeError(format!("Invalid indent options. {}", e)))?,
+            };
+            let prefix = options.prefix.unwrap_or(DEFAULT_INDENT_PREFIX.to_string());
+            Ok(indent(&value, &prefix, options.first.unwrap_or(false), options.blank.unwrap_or(false)))
+        });
+
+        methods.add_function("dedent", |_, (_ud, value): (AnyUserData, String)| {
+            Ok(dedent(&value))
+        });
+
+        methods.add_function("wrap", |_, (_ud, value, width): (AnyUserData, String, Option<usize>)| {
+            Ok(wrap(&value, width.unwrap_or(DEFAULT_WRAP_WIDTH)))
+        });
+    }
+}
@@ -1,5 +1,6 @@
 use mlua::{AnyUserData, LuaSerdeExt, UserData, UserDataMethods};
 use mlua::Error::RuntimeError;
+use serde::Deserialize;
 use serde_yaml::Value;
 
 use crate::types::AppResult;
@@ -12,6 +13,19 @@ impl LuauYaml {
         let output = serde_yaml::to_string(value)?;
         return Ok(output)
     }
+
+    fn parse(source: &str) -> AppResult<Value> {
+        let value = serde_yaml::from_str(source)?;
+        Ok(value)
+    }
+
+    fn parse_all(source: &str) -> AppResult<Vec<Value>> {
+        let mut documents: Vec<Value> = vec![];
+        for document in serde_yaml::Deserializer::from_str(source) {
+            documents.push(Value::deserialize(document)?);
+        }
+        Ok(documents)
+    }
 }
 
 impl UserData for LuauYaml {
@@ -22,6 +36,20 @@ impl UserData for LuauYaml {
                 Ok(yaml) => Self::stringify(&yaml).or_else(|e| Err(RuntimeError(format!("Cannot serialize value. {}", e).to_string()))),
                 Err(e) => return Err(RuntimeError(format!("Cannot serialize value. {}", e).to_string())),
             }
-        })
+        });
+
+        methods.add_function("parse", |lua, (_ud, source): (AnyUserData, String)| {
+            match Self::parse(&source) {
+                Ok(yaml) => lua.to_value(&yaml),
+                Err(e) => Err(RuntimeError(format!("Cannot parse YAML. {}", e))),
+            }
+        });
+
+        methods.add_function("parse_all", |lua, (_ud, source): (AnyUserData, String)| {
+            match Self::parse_all(&source) {
+                Ok(documents) => lua.to_value(&documents),
+                Err(e) => Err(RuntimeError(format!("Cannot parse YAML. {}", e))),
+            }
+        });
     }
 }
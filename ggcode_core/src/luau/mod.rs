@@ -1,3 +1,10 @@
 pub mod luau_yaml;
 pub mod luau_json;
 pub mod luau_uuid;
+pub mod luau_case;
+pub mod luau_str;
+pub mod luau_toml;
+pub mod luau_dotenv;
+pub mod luau_ini;
+pub mod luau_env;
+pub mod luau_prompt;
@@ -4,6 +4,9 @@ use std::path::PathBuf;
 use serde::Serialize;
 use serde_yaml::{to_value, Value};
 
+use crate::capability::Sandbox;
+use crate::renderer::luau_extras::LuauLimits;
+
 #[derive(Default)]
 pub struct RendererBuilder {
     pub values: BTreeMap<String, Value>,
@@ -11,6 +14,9 @@ pub struct RendererBuilder {
     pub raw_templates: BTreeMap<String, String>,
     // file_scripts: BTreeMap<String, String>,
     pub file_templates: BTreeMap<String, PathBuf>,
+    pub library_templates: BTreeMap<String, PathBuf>,
+    pub luau_limits: LuauLimits,
+    pub sandbox: Option<Sandbox>,
 }
 
 impl RendererBuilder {
@@ -33,4 +39,20 @@ impl RendererBuilder {
         self.file_templates.insert(name.into(), path.into());
         self
     }
+
+    pub fn with_luau_limits(mut self, limits: LuauLimits) -> RendererBuilder {
+        self.luau_limits = limits;
+        self
+    }
+
+    /// Restricts templates to the capabilities of the sandbox; without one they are trusted.
+    pub fn with_sandbox(mut self, sandbox: Option<Sandbox>) -> RendererBuilder {
+        self.sandbox = sandbox;
+        self
+    }
+
+    pub fn with_library_template<S: Into<String>, V: Into<PathBuf>>(mut self, name: S, path: V) -> RendererBuilder {
+        self.library_templates.insert(name.into(), path.into());
+        self
+    }
 }
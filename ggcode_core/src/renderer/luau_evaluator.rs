@@ -5,15 +5,27 @@ use mlua::{Lua, LuaSerdeExt, Table};
 use serde::Serialize;
 use serde_yaml::{to_value, Value};
 
+use crate::luau::luau_case::LuauCase;
+use crate::capability::Sandbox;
+use crate::config::Capability;
+use crate::luau::luau_dotenv::LuauDotenv;
+use crate::luau::luau_env::LuauEnv;
+use crate::luau::luau_ini::LuauIni;
 use crate::luau::luau_json::LuauJson;
+use crate::luau::luau_prompt::LuauPrompt;
+use crate::luau::luau_str::LuauStr;
+use crate::luau::luau_toml::LuauToml;
 use crate::luau::luau_uuid::LuauUuid;
 use crate::luau::luau_yaml::LuauYaml;
-use crate::renderer::luau_extras::{LuauEngine, LuauShell, trace_mlua_error};
-use crate::types::{AppResult, ErrorBox};
+use crate::renderer::luau_extras::{LuauDenied, LuauEngine, LuauFs, LuauLimits, LuauShell};
+use crate::error::ScriptKind;
+use crate::types::AppResult;
 
 #[derive(Default)]
 pub struct LuauEvaluator {
-    lua: Lua
+    lua: Lua,
+    limits: LuauLimits,
+    kind: ScriptKind,
 }
 
 #[derive(Default)]
@@ -21,7 +33,12 @@ pub struct LuauEvaluatorBuilder {
     pub globals: BTreeMap<String, Value>,
     pub paths: Vec<PathBuf>,
     pub shell: Option<LuauShell>,
+    pub fs: Option<LuauFs>,
     pub engine: Option<LuauEngine>,
+    pub prompt: Option<LuauPrompt>,
+    pub sandbox: Option<Sandbox>,
+    pub limits: LuauLimits,
+    pub kind: ScriptKind,
     // pub(crate) template: Option<LuauTemplate>,
 }
 
@@ -40,11 +57,38 @@ impl LuauEvaluatorBuilder {
         self
     }
 
+    pub fn enable_fs(mut self, fs: LuauFs) -> LuauEvaluatorBuilder {
+        self.fs = Some(fs);
+        self
+    }
+
+    /// Restricts the script to the capabilities of the sandbox; without one the script is trusted.
+    pub fn with_sandbox(mut self, sandbox: Option<Sandbox>) -> LuauEvaluatorBuilder {
+        self.sandbox = sandbox;
+        self
+    }
+
+    pub fn with_limits(mut self, limits: LuauLimits) -> LuauEvaluatorBuilder {
+        self.limits = limits;
+        self
+    }
+
+    /// Tells what the scripts are run for, failures are worded after it.
+    pub fn with_kind(mut self, kind: ScriptKind) -> LuauEvaluatorBuilder {
+        self.kind = kind;
+        self
+    }
+
     pub fn enable_engine(mut self, engine: LuauEngine) -> LuauEvaluatorBuilder {
         self.engine = Some(engine);
         self
     }
 
+    pub fn enable_prompt(mut self, prompt: LuauPrompt) -> LuauEvaluatorBuilder {
+        self.prompt = Some(prompt);
+        self
+    }
+
     // pub fn enable_template(mut self, template: LuauTemplate) -> LuauEvaluatorBuilder {
     //     self.template = Some(template);
     //     self
@@ -79,37 +123,87 @@ impl LuauEvaluatorBuilder {
             globals.set("yaml", lua.create_userdata(LuauYaml)?)?;
             globals.set("json", lua.create_userdata(LuauJson)?)?;
             globals.set("uuid", lua.create_userdata(LuauUuid)?)?;
+            globals.set("case", lua.create_userdata(LuauCase)?)?;
+            globals.set("str", lua.create_userdata(LuauStr)?)?;
+            globals.set("toml", lua.create_userdata(LuauToml)?)?;
+            globals.set("dotenv", lua.create_userdata(LuauDotenv)?)?;
+            globals.set("ini", lua.create_userdata(LuauIni)?)?;
+
+            match self.denied(Capability::Env, "environment variables") {
+                Some(denied) => globals.set("env", lua.create_userdata(denied)?)?,
+                None => globals.set("env", lua.create_userdata(LuauEnv)?)?,
+            }
 
             if let Some(shell) = &self.shell {
-                let userdata = lua.create_userdata(shell.clone())?;
-                globals.set("shell", userdata)?;
+                match self.denied(Capability::Shell, "shell commands") {
+                    Some(denied) => globals.set("shell", lua.create_userdata(denied)?)?,
+                    None => globals.set("shell", lua.create_userdata(shell.clone())?)?,
+                }
+            }
+
+            if let Some(fs) = &self.fs {
+                let mut fs = fs.clone();
+                fs.sandbox = self.sandbox.clone();
+                match self.denied(Capability::FsRead, "the filesystem") {
+                    Some(denied) => globals.set("fs", lua.create_userdata(denied)?)?,
+                    None => globals.set("fs", lua.create_userdata(fs)?)?,
+                }
             }
 
             if let Some(engine) = &self.engine {
-                let userdata = lua.create_userdata(engine.clone())?;
+                let mut engine = engine.clone();
+                engine.sandbox = self.sandbox.clone();
+                let userdata = lua.create_userdata(engine)?;
                 globals.set("engine", userdata)?;
             }
 
+            if let Some(prompt) = &self.prompt {
+                globals.set("prompt", lua.create_userdata(prompt.clone())?)?;
+            }
+
             globals
                 .get::<_, Table>("package")?
                 .set("path", search_path.clone())?;
         }
 
+        if self.sandbox.is_some() {
+            lua.sandbox(true)?;
+        }
+
         let evaluator = LuauEvaluator {
-            lua
+            lua,
+            limits: self.limits,
+            kind: self.kind,
         };
         Ok(evaluator)
     }
 }
 
+impl LuauEvaluatorBuilder {
+    fn denied(&self, capability: Capability, operation: &str) -> Option<LuauDenied> {
+        match &self.sandbox {
+            Some(sandbox) if !sandbox.allows(capability) => Some(LuauDenied {
+                message: sandbox.missing(capability, operation).to_string(),
+            }),
+            _ => None,
+        }
+    }
+}
+
 impl LuauEvaluator {
     pub fn eval_value(&self, script: &String) -> AppResult<Value> {
+        self.eval_file_value("script", script)
+    }
+
+    /// Evaluates the content of a script file, errors and tracebacks refer to it by its path.
+    pub fn eval_file_value(&self, path: &str, script: &String) -> AppResult<Value> {
+        // Limits are counted from the start of every evaluation
+        self.limits.apply(&self.lua)?;
+
         let config_lua: mlua::Value = self.lua.load(script)
+            .set_name(format!("@{}", path))
             .eval::<mlua::Value>()
-            .map_err::<ErrorBox, _>(|e| {
-                trace_mlua_error(script, &e.clone().into());
-                format!("Error parsing template: {e}").into()
-            })?;
+            .map_err(|e| self.limits.script_error(self.kind, path, script, e))?;
         let config = self.lua.from_value::<Value>(config_lua)?;
         Ok(config)
     }
@@ -117,37 +211,451 @@ impl LuauEvaluator {
 
 #[cfg(test)]
 mod tests {
+    use std::collections::BTreeSet;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
     use serde_yaml::Value;
 
+    use crate::capability::Sandbox;
+    use crate::config::{Capability, LimitsEntry, PackageConfig, TargetEntry};
+    use crate::generator::{DefaultGenerator, GeneratorEvent, GeneratorObserver};
     use crate::renderer::luau_evaluator::LuauEvaluatorBuilder;
-    use crate::renderer::luau_extras::{LuauShell, LuauTemplate};
+    use crate::renderer::luau_extras::{LuauFs, LuauLimits, LuauShell};
+    use crate::ResolvedContext;
     use crate::types::AppResult;
+    use crate::utils::errors::{describe_error, describe_mlua_message, ErrorDescription};
 
     #[test]
     fn eval_value_test() -> AppResult<()> {
         let evaluator = LuauEvaluatorBuilder::new()
             .with_global("one", "1")
             .with_global("two", "2")
-            .enable_shell(LuauShell)
-            .enable_template(LuauTemplate { st: String::new() })
+            .enable_shell(LuauShell::default())
             .build()?;
 
         let actual = evaluator.eval_value(&"`{one}/{two}`".into())?;
         let expected = Value::String("1/2".into());
         assert_eq!(actual, expected);
 
-        let actual = evaluator.eval_value(&"shell:exec(`echo 'Hello'`)".into())?;
-        let expected = Value::String("Hello".into());
+        let actual = evaluator.eval_value(&"shell:exec('.', `echo 'Hello'`)".into())?;
+        let expected = Value::String("Hello\n".into());
         assert_eq!(actual, expected);
 
         Ok(())
     }
 
+    #[cfg(unix)]
+    #[test]
+    fn eval_value_shell_run_test() -> AppResult<()> {
+        let evaluator = LuauEvaluatorBuilder::new()
+            .enable_shell(LuauShell::default())
+            .build()?;
+
+        let script = r#"
+            local result = shell:run({
+                cmd = "cat; echo \"$GREETING\" >&2; exit 3",
+                cwd = "src",
+                env = { GREETING = "Hello" },
+                stdin = "Input",
+            })
+            return result
+        "#;
+
+        let actual = evaluator.eval_value(&script.into())?;
+        assert_eq!(actual["code"], Value::from(3));
+        assert_eq!(actual["stdout"], Value::from("Input"));
+        assert_eq!(actual["stderr"], Value::from("Hello\n"));
+
+        assert!(evaluator.eval_value(&"shell:run({ cmd = 'exit 1', check = true })".into()).is_err());
+        assert!(evaluator.eval_value(&"shell:run({ cmd = 'sleep 5', timeout = 0.1 })".into()).is_err());
+
+        Ok(())
+    }
+
+    struct RecordingObserver {
+        lines: Vec<String>,
+    }
+
+    impl GeneratorObserver for RecordingObserver {
+        fn on_notify(&mut self, event: &GeneratorEvent) {
+            if let GeneratorEvent::Output(line) = event {
+                self.lines.push(line.clone());
+            }
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn eval_value_shell_stream_test() -> AppResult<()> {
+        let observer = Arc::new(Mutex::new(RecordingObserver { lines: vec![] }));
+        let mut generator = DefaultGenerator { context: test_context()?, wrapped_observers: vec![] };
+        generator.add_observer(observer.clone());
+
+        let evaluator = LuauEvaluatorBuilder::new()
+            .enable_shell(LuauShell::default().with_generator(generator))
+            .build()?;
+
+        let script = "return shell:run({ cmd = 'echo one; echo two', stream = true }).stdout";
+        let actual = evaluator.eval_value(&script.into())?;
+        assert_eq!(actual, Value::from("one\ntwo\n"));
+        assert_eq!(observer.lock().unwrap().lines, vec!["one", "two"]);
+
+        // `shell:exec` streams its output the same way
+        let actual = evaluator.eval_value(&"shell:exec('.', 'echo three; echo four')".into())?;
+        assert_eq!(actual, Value::from("three\nfour\n"));
+        assert_eq!(observer.lock().unwrap().lines, vec!["one", "two", "three", "four"]);
+
+        Ok(())
+    }
+
+    fn test_context() -> AppResult<ResolvedContext> {
+        Ok(ResolvedContext {
+            config_path: "ggcode-info.yaml".to_string(),
+            directory_path: std::env::current_dir()?.canonicalize()?,
+            directory_name: "ggcode_core".to_string(),
+            current_config: PackageConfig {
+                name: "ggcode_core".to_string(),
+                actions: vec![],
+                scrolls: vec![],
+                repositories: vec![],
+                targets: vec![],
+                limits: None,
+            },
+        })
+    }
+
+    #[test]
+    fn eval_value_sandbox_test() -> AppResult<()> {
+        let base_dir = std::env::current_dir()?.canonicalize()?;
+        let sandbox = Sandbox {
+            repository: "core".to_string(),
+            capabilities: BTreeSet::from([Capability::FsRead]),
+        };
+        let evaluator = LuauEvaluatorBuilder::new()
+            .enable_shell(LuauShell::default())
+            .enable_fs(LuauFs { roots: vec![base_dir], writer: None, sandbox: None })
+            .with_sandbox(Some(sandbox))
+            .build()?;
+
+        let actual = evaluator.eval_value(&"return fs:exists('Cargo.toml')".into())?;
+        assert_eq!(actual, Value::from(true));
+
+        let error = evaluator.eval_value(&"return shell:exec('.', 'echo Hello')".into()).unwrap_err();
+        assert!(error.to_string().contains("Missing capability"));
+        assert!(error.to_string().contains("shell"));
+        assert!(evaluator.eval_value(&"return env:get('PATH')".into()).is_err());
+
+        let error = evaluator.eval_value(&"fs:write('ggcode-sandbox-test.txt', 'Hello')".into()).unwrap_err();
+        assert!(error.to_string().contains("Missing capability"));
+        assert!(error.to_string().contains("fs-write"));
+
+        let trusted = LuauEvaluatorBuilder::new().build()?;
+        assert!(trusted.eval_value(&"return env:get('PATH')".into())?.is_string());
+
+        Ok(())
+    }
+
+    #[test]
+    fn eval_value_error_description_test() -> AppResult<()> {
+        let evaluator = LuauEvaluatorBuilder::new()
+            .enable_shell(LuauShell::default())
+            .build()?;
+
+        let error = evaluator.eval_file_value("build.luau", &"local x = 1\nshell:exec(1, {})".into()).unwrap_err();
+        let Some(ErrorDescription::SourceError(data)) = describe_error(&error)? else {
+            panic!("Callback error should be located: {}", error);
+        };
+        assert_eq!((data.location.as_str(), data.line), ("build.luau", 1));
+        assert!(data.details.starts_with("Bad argument #2 to LuauShell.exec. Cannot convert Luau table to String."));
+        assert!(data.traceback.is_some());
+
+        let error = evaluator.eval_file_value("build.luau", &"local function f()\n  error('boom')\nend\nf()".into()).unwrap_err();
+        let Some(ErrorDescription::SourceError(data)) = describe_error(&error)? else {
+            panic!("Runtime error should be located: {}", error);
+        };
+        assert_eq!((data.line, data.details.as_str()), (1, "boom"));
+        assert!(data.traceback.unwrap().contains("build.luau:4: in ?"));
+        assert!(!error.to_string().contains("stack traceback"));
+
+        let nested = mlua::Error::CallbackError {
+            traceback: "stack traceback:\n\t[C]: in ?".to_string(),
+            cause: Arc::new(mlua::Error::WithContext {
+                context: "Cannot load module".to_string(),
+                cause: Arc::new(mlua::Error::UserDataBorrowMutError),
+            }),
+        };
+        assert_eq!(describe_mlua_message(&nested), "Cannot load module. A module is already in use by a running method.");
+        Ok(())
+    }
+
+    #[test]
+    fn eval_value_limits_test() -> AppResult<()> {
+        let timeout = LuauEvaluatorBuilder::new()
+            .with_limits(LuauLimits { timeout: Some(Duration::from_millis(100)), ..LuauLimits::unlimited() })
+            .build()?;
+        let error = timeout.eval_value(&"local a = 1\nwhile true do a += 1 end".into()).unwrap_err();
+        assert!(error.to_string().contains("time limit"));
+        assert!(error.to_string().contains(":2:"));
+
+        // Time spent in shell commands is not counted
+        let shell = LuauEvaluatorBuilder::new()
+            .with_limits(LuauLimits { timeout: Some(Duration::from_millis(200)), ..LuauLimits::unlimited() })
+            .enable_shell(LuauShell::default())
+            .build()?;
+        let waited = shell.eval_value(&"shell:run({ cmd = 'sleep 0.5' })\nfor i = 1, 10 do end\nreturn true".into())?;
+        assert_eq!(waited, Value::from(true));
+
+        let interrupts = LuauEvaluatorBuilder::new()
+            .with_limits(LuauLimits { interrupts: Some(1000), ..LuauLimits::unlimited() })
+            .build()?;
+        let error = interrupts.eval_value(&"for i = 1, 100000 do end".into()).unwrap_err();
+        assert!(error.to_string().contains("limit of 1000 function calls and loop iterations"));
+
+        let memory = LuauEvaluatorBuilder::new()
+            .with_limits(LuauLimits { memory: Some(4 * 1024 * 1024), ..LuauLimits::unlimited() })
+            .build()?;
+        let error = memory.eval_value(&"local t = {}\nfor i = 1, 10000000 do t[i] = i end".into()).unwrap_err();
+        assert!(error.to_string().contains("memory limit of 4 MiB"));
+
+        assert_eq!(interrupts.eval_value(&"return 1 + 1".into())?, Value::from(2));
+
+        let entry = LimitsEntry { timeout: Some(0.0), memory: Some(64), interrupts: None };
+        let configured = LuauLimits::configured(Some(&entry));
+        assert_eq!(configured.timeout, None);
+        assert_eq!(configured.memory, Some(64 * 1024 * 1024));
+        assert_eq!(configured.interrupts, None);
+        assert_eq!(LuauLimits::configured(None).timeout, LuauLimits::default().timeout);
+
+        Ok(())
+    }
+
+    #[test]
+    fn eval_value_strings_test() -> AppResult<()> {
+        let evaluator = LuauEvaluatorBuilder::new()
+            .with_global("name", "user account")
+            .build()?;
+
+        let script = "`{case:pascal(name)} {case:screaming(name)} {str:plural(case:kebab(name))} {str:indent('a\\nb', { prefix = '  ' })}`";
+        let actual = evaluator.eval_value(&script.into())?;
+        let expected = Value::String("UserAccount USER_ACCOUNT user-accounts a\n  b".into());
+        assert_eq!(actual, expected);
+
+        Ok(())
+    }
+
+    #[test]
+    fn eval_value_parse_test() -> AppResult<()> {
+        let evaluator = LuauEvaluatorBuilder::new().build()?;
+
+        let script = r#"
+            local config = yaml:parse("name: app\nports: [80, 443]\nowner: ~")
+            local documents = yaml:parse_all("---\nkind: a\n---\nkind: b\n")
+            local package = json:parse('{"name": "app", "tags": [], "main": null}')
+            return {
+                name = config.name,
+                port = config.ports[2],
+                owner_is_null = config.owner == null,
+                kinds = `{documents[1].kind}{documents[2].kind}`,
+                tags = json:stringify(package.tags, { pretty = false }),
+                main_is_null = package.main == null,
+                compact = json:stringify({ a = 1 }, { pretty = false }),
+            }
+        "#;
+
+        let actual = evaluator.eval_value(&script.into())?;
+        assert_eq!(actual["name"], Value::from("app"));
+        assert_eq!(actual["port"], Value::from(443));
+        assert_eq!(actual["owner_is_null"], Value::from(true));
+        assert_eq!(actual["kinds"], Value::from("ab"));
+        assert_eq!(actual["tags"], Value::from("[]"));
+        assert_eq!(actual["main_is_null"], Value::from(true));
+        assert_eq!(actual["compact"], Value::from("{\"a\":1}"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn eval_value_stringify_error_test() -> AppResult<()> {
+        let evaluator = LuauEvaluatorBuilder::new().build()?;
+
+        let script = r#"
+            local _, toml_error = pcall(function() return toml:stringify("app") end)
+            local _, dotenv_error = pcall(function() return dotenv:stringify({ nested = { a = 1 } }) end)
+            local _, ini_error = pcall(function() return ini:stringify("app") end)
+            return { toml = tostring(toml_error), dotenv = tostring(dotenv_error), ini = tostring(ini_error) }
+        "#;
+
+        let actual = evaluator.eval_value(&script.into())?;
+        for (format, message) in [
+            ("toml", "Cannot serialize value. Only tables can be represented as TOML documents."),
+            ("dotenv", "Cannot serialize value. Expected a scalar, got: a: 1"),
+            ("ini", "Cannot serialize value. Only tables can be represented as INI files."),
+        ] {
+            let error = actual[format].as_str().unwrap();
+            assert!(error.contains(message), "{}", error);
+            assert_eq!(error.matches("Cannot serialize value.").count(), 1, "{}", error);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn eval_value_fs_test() -> AppResult<()> {
+        let base_dir = std::env::current_dir()?.canonicalize()?;
+        let evaluator = LuauEvaluatorBuilder::new()
+            .enable_fs(LuauFs { roots: vec![base_dir], writer: None, sandbox: None })
+            .build()?;
+
+        let script = r#"
+            local package = toml:parse(fs:read("Cargo.toml")).package
+            local sources = fs:walk("src/luau")
+            return {
+                name = package.name,
+                has_manifest = fs:exists("./Cargo.toml"),
+                has_missing = fs:exists("src/missing.rs"),
+                listed = table.find(fs:list("src"), "lib.rs") ~= nil,
+                globbed = table.find(fs:glob("src/luau/*.rs"), "src/luau/mod.rs") ~= nil,
+                walked = table.find(sources, "src/luau/mod.rs") ~= nil,
+                kind = fs:stat("src").kind,
+            }
+        "#;
+
+        let actual = evaluator.eval_value(&script.into())?;
+        assert_eq!(actual["name"], Value::from("ggcode_core"));
+        assert_eq!(actual["has_manifest"], Value::from(true));
+        assert_eq!(actual["has_missing"], Value::from(false));
+        assert_eq!(actual["listed"], Value::from(true));
+        assert_eq!(actual["globbed"], Value::from(true));
+        assert_eq!(actual["walked"], Value::from(true));
+        assert_eq!(actual["kind"], Value::from("dir"));
+
+        assert!(evaluator.eval_value(&"fs:read('../Cargo.toml')".into()).is_err());
+        assert!(evaluator.eval_value(&"fs:list('/')".into()).is_err());
+        assert!(evaluator.eval_value(&"fs:glob('/*')".into()).is_err());
+        assert!(evaluator.eval_value(&"fs:glob('src/../../*')".into()).is_err());
+
+        // Patterns are matched against the project directory rather than the current directory
+        let nested_evaluator = LuauEvaluatorBuilder::new()
+            .enable_fs(LuauFs { roots: vec![std::env::current_dir()?.canonicalize()?.join("src")], writer: None, sandbox: None })
+            .build()?;
+        let globbed = nested_evaluator.eval_value(&"fs:glob('luau/*.rs')".into())?;
+        assert!(globbed.as_sequence().is_some_and(|paths| paths.contains(&Value::from("luau/mod.rs"))));
+
+        Ok(())
+    }
+
+    #[test]
+    fn fs_missing_target_test() -> AppResult<()> {
+        let missing_dir = std::env::temp_dir().join(format!("ggcode-missing-{}", std::process::id())).join("out");
+        let mut context = test_context()?;
+        context.current_config.targets = vec![TargetEntry {
+            name: "missing".to_string(),
+            path: missing_dir.to_string_lossy().to_string(),
+        }];
+
+        let fs = LuauFs::new(&context)?;
+        assert!(fs.resolve(&missing_dir.join("file.txt").to_string_lossy()).is_ok());
+        Ok(())
+    }
+
+    #[test]
+    fn eval_value_fs_write_test() -> AppResult<()> {
+        let base_dir = std::env::current_dir()?.canonicalize()?;
+        let target_dir = std::env::temp_dir().join(format!("ggcode-fs-write-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&target_dir);
+        std::fs::create_dir_all(&target_dir)?;
+        let target = target_dir.to_string_lossy().to_string();
+
+        let generator = DefaultGenerator { context: test_context()?, wrapped_observers: vec![] };
+        let fs = LuauFs { roots: vec![base_dir], writer: None, sandbox: None }.with_root(&target_dir);
+
+        let evaluator = LuauEvaluatorBuilder::new()
+            .with_global("target", &Value::from(target.clone()))
+            .enable_fs(fs.clone().with_writer(generator.clone(), false))
+            .build()?;
+
+        let script = r#"
+            fs:mkdir(target .. "/src")
+            fs:write(target .. "/src/main.txt", "Hello")
+            fs:write(target .. "/src/main.txt", "Ignored", { overwrite = false })
+            fs:copy(target .. "/src", target .. "/copy")
+            fs:move(target .. "/copy/main.txt", target .. "/moved.txt")
+            fs:remove(target .. "/src")
+            return {
+                moved = fs:read(target .. "/moved.txt"),
+                removed = not fs:exists(target .. "/src"),
+            }
+        "#;
+
+        let actual = evaluator.eval_value(&script.into())?;
+        assert_eq!(actual["moved"], Value::from("Hello"));
+        assert_eq!(actual["removed"], Value::from(true));
+        assert!(evaluator.eval_value(&"fs:write('../escape.txt', 'Hello')".into()).is_err());
+        assert!(evaluator.eval_value(&"fs:remove(target)".into()).is_err());
+
+        std::fs::create_dir_all(target_dir.join(".git"))?;
+        std::fs::write(target_dir.join("ggcode-info.yaml"), "")?;
+        for script in [
+            "fs:remove(target .. '/.git')",
+            "fs:mkdir(target .. '/.git/hooks')",
+            "fs:mkdir(target .. '/ggcode-info.yaml/nested')",
+            "fs:write(target .. '/.git/config', '')",
+            "fs:move(target .. '/moved.txt', target .. '/ggcode-info.yaml')",
+            "fs:move(target .. '/ggcode-info.yaml', target .. '/moved.txt')",
+            "fs:copy(target .. '/moved.txt', target .. '/.git/HEAD')",
+        ] {
+            let error = evaluator.eval_value(&script.into()).unwrap_err();
+            assert!(error.to_string().contains("cannot be changed"), "{}: {}", script, error);
+        }
+        assert!(target_dir.join(".git").exists());
+        assert!(target_dir.join("ggcode-info.yaml").exists());
+        evaluator.eval_value(&"fs:mkdir(target)".into())?;
+
+        // Symbolic links are resolved before checking the roots, also for paths to be created
+        #[cfg(unix)]
+        {
+            let outside_dir = std::env::temp_dir().join(format!("ggcode-fs-outside-{}", std::process::id()));
+            std::fs::create_dir_all(&outside_dir)?;
+            std::os::unix::fs::symlink(&outside_dir, target_dir.join("out"))?;
+            std::fs::create_dir_all(target_dir.join("linked"))?;
+            std::os::unix::fs::symlink(&outside_dir, target_dir.join("linked/out"))?;
+            for script in [
+                "fs:write(target .. '/out/x.txt', 'Hello')",
+                "fs:mkdir(target .. '/out/x')",
+                "fs:copy(target .. '/moved.txt', target .. '/out/x.txt')",
+                "fs:copy(target .. '/linked', target .. '/linked-copy')",
+            ] {
+                assert!(evaluator.eval_value(&script.into()).is_err(), "{}", script);
+            }
+            assert_eq!(std::fs::read_dir(&outside_dir)?.count(), 0);
+            std::fs::remove_dir_all(&outside_dir)?;
+        }
+        evaluator.eval_value(&"fs:mkdir(target .. '/tree')".into())?;
+        let error = evaluator.eval_value(&"fs:copy(target .. '/tree', target .. '/tree/nested')".into()).unwrap_err();
+        assert!(error.to_string().contains("into itself"));
+
+        let dry_evaluator = LuauEvaluatorBuilder::new()
+            .with_global("target", &Value::from(target.clone()))
+            .enable_fs(fs.clone().with_writer(generator, true))
+            .build()?;
+        dry_evaluator.eval_value(&"fs:remove(target .. '/moved.txt')".into())?;
+        assert!(target_dir.join("moved.txt").exists());
+
+        let read_only_evaluator = LuauEvaluatorBuilder::new()
+            .with_global("target", &Value::from(target))
+            .enable_fs(fs)
+            .build()?;
+        assert!(read_only_evaluator.eval_value(&"fs:mkdir(target .. '/src')".into()).is_err());
+
+        std::fs::remove_dir_all(&target_dir)?;
+        Ok(())
+    }
+
     #[test]
     fn eval_value_failure_test() -> AppResult<()> {
         let evaluator = LuauEvaluatorBuilder::new()
-            .enable_shell(LuauShell)
-            .enable_template(LuauTemplate { st: String::new() })
+            .enable_shell(LuauShell::default())
             .build()?;
 
         let script = "
@@ -163,10 +671,9 @@ mod tests {
             }
         ";
 
-        let actual = evaluator.eval_value(&script.into())?;
-        let expected = Value::String("1/2".into());
-        assert_eq!(actual, expected);
+        let actual = evaluator.eval_value(&script.into());
+        assert!(actual.is_err());
 
         Ok(())
     }
-}
\ No newline at end of file
+}
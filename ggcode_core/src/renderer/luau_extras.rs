@@ -1,22 +1,158 @@
-use std::{cmp, fs, thread};
-use std::collections::HashMap;
-use std::error::Error;
-use std::io::{BufRead, BufReader};
-use std::path::PathBuf;
-use std::sync::mpsc::sync_channel;
-
-use console::{Color, style};
-use mlua::{AnyUserData, LuaSerdeExt, UserData, UserDataMethods};
+use std::{fs, thread};
+use std::collections::BTreeMap;
+use std::ffi::OsStr;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::path::{Component, Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{channel, RecvTimeoutError, Sender};
+use std::time::{Duration, Instant, UNIX_EPOCH};
+
+use glob::{glob, Pattern};
+use mlua::{AnyUserData, Lua, LuaSerdeExt, MetaMethod, UserData, UserDataMethods, VmState};
 use mlua::Error::RuntimeError;
-use run_script::ScriptOptions;
+use relative_path::{RelativePath, RelativePathBuf};
 use serde::{Deserialize, Serialize};
 use serde_yaml::Value;
 
-use crate::generator::DefaultGenerator;
+use crate::action::{ActionRef, ActionRunner, find_action_by_full_name};
+use crate::capability::Sandbox;
+use crate::config::{Capability, DEFAULT_CONFIG_NAME, LimitsEntry, TargetEntry};
+use crate::generator::{DefaultGenerator, GeneratorEvent};
+use crate::error::{Error, ScriptKind};
+use crate::generator::GeneratorEvent::{Failure, Finish, Output, Skip, Start};
 use crate::ResolvedContext;
-use crate::storage::{resolve_target};
+use crate::scroll::list_scrolls;
+use crate::storage::{load_file_mode, parse_file_mode, resolve_target, resolve_target_path, save_file_mode};
 use crate::types::AppResult;
-use crate::utils::errors::{describe_error, ErrorDescription};
+use crate::utils::errors::describe_mlua_message;
+
+pub const DEFAULT_LUAU_TIMEOUT: Duration = Duration::from_secs(60);
+pub const DEFAULT_LUAU_MEMORY: usize = 512 * 1024 * 1024;
+
+/// Execution limits of a Luau state. Interrupts happen on function calls and loop iterations,
+/// so their limit bounds those rather than single VM instructions. The timeout only counts
+/// the time spent in Luau, commands run through `shell` are not part of it.
+#[derive(Debug, Clone, Copy)]
+pub struct LuauLimits {
+    pub interrupts: Option<u64>,
+    pub timeout: Option<Duration>,
+    pub memory: Option<usize>,
+}
+
+impl Default for LuauLimits {
+    fn default() -> Self {
+        LuauLimits {
+            interrupts: None,
+            timeout: Some(DEFAULT_LUAU_TIMEOUT),
+            memory: Some(DEFAULT_LUAU_MEMORY),
+        }
+    }
+}
+
+/// Time a script spent waiting outside of Luau, which is not counted against its timeout.
+#[derive(Default)]
+struct ExcludedTime(Duration);
+
+impl LuauLimits {
+    pub fn unlimited() -> LuauLimits {
+        LuauLimits {
+            interrupts: None,
+            timeout: None,
+            memory: None,
+        }
+    }
+
+    /// Limits configured by the project on top of the defaults, zero disables a limit.
+    pub fn configured(entry: Option<&LimitsEntry>) -> LuauLimits {
+        let defaults = LuauLimits::default();
+        let Some(entry) = entry else {
+            return defaults;
+        };
+        LuauLimits {
+            interrupts: entry.interrupts.or(defaults.interrupts).filter(|interrupts| *interrupts > 0),
+            timeout: entry.timeout.map(Duration::from_secs_f64).or(defaults.timeout).filter(|timeout| !timeout.is_zero()),
+            memory: entry.memory.map(|memory| memory * 1024 * 1024).or(defaults.memory).filter(|memory| *memory > 0),
+        }
+    }
+
+    pub fn apply(&self, lua: &Lua) -> AppResult<()> {
+        if let Some(memory) = self.memory {
+            lua.set_memory_limit(memory)?;
+        }
+
+        if self.interrupts.is_none() && self.timeout.is_none() {
+            return Ok(());
+        }
+
+        let limits = *self;
+        let started = Instant::now();
+        let counter = AtomicU64::new(0);
+        lua.set_app_data(ExcludedTime::default());
+        lua.set_interrupt(move |lua| {
+            let count = counter.fetch_add(1, Ordering::Relaxed) + 1;
+            if limits.interrupts.is_some_and(|interrupts| count > interrupts) {
+                return Err(RuntimeError(locate_interrupt(lua, format!(
+                    "Script exceeded the limit of {} function calls and loop iterations. Check it for infinite loops or recursion.",
+                    limits.interrupts.unwrap_or_default()))));
+            }
+            let excluded = lua.app_data_ref::<ExcludedTime>().map(|time| time.0).unwrap_or_default();
+            if limits.timeout.is_some_and(|timeout| started.elapsed().saturating_sub(excluded) > timeout) {
+                return Err(RuntimeError(locate_interrupt(lua, format!(
+                    "Script exceeded the time limit of {} seconds. Check it for infinite loops.",
+                    limits.timeout.unwrap_or_default().as_secs_f64()))));
+            }
+            Ok(VmState::Continue)
+        });
+        Ok(())
+    }
+
+    /// Makes errors caused by exceeding a limit readable, other errors are described as is.
+    pub fn describe_error(&self, e: &mlua::Error) -> String {
+        match e {
+            mlua::Error::MemoryError(_) => format!(
+                "Script exceeded the memory limit of {} MiB. Check it for unbounded tables or strings.",
+                self.memory.unwrap_or_default() / (1024 * 1024)),
+            mlua::Error::CallbackError { cause, .. } => self.describe_error(cause),
+            _ => describe_mlua_message(e),
+        }
+    }
+
+    /// Wraps a failed evaluation, keeping the source so callers can point at the failing line.
+    pub fn script_error<N: Into<String>>(&self, kind: ScriptKind, name: N, script: &str, e: mlua::Error) -> Error {
+        Error::Script {
+            kind,
+            name: name.into(),
+            source_code: script.to_string(),
+            message: self.describe_error(&e),
+            cause: Box::new(e),
+        }
+    }
+}
+
+/// Runs a callback waiting outside of Luau, without counting it against the timeout of the script.
+fn excluding_time<T, F: FnOnce() -> T>(lua: &Lua, callback: F) -> T {
+    let started = Instant::now();
+    let result = callback();
+    if let Some(mut excluded) = lua.app_data_mut::<ExcludedTime>() {
+        excluded.0 += started.elapsed();
+    }
+    result
+}
+
+/// Prefixes the message with the chunk and the line being executed, so it is traced like any other script error.
+fn locate_interrupt(lua: &Lua, message: String) -> String {
+    match lua.inspect_stack(0) {
+        Some(debug) => {
+            let line = debug.curr_line();
+            match (debug.source().short_src, line > 0) {
+                (Some(source), true) => format!("{}:{}: {}", source, line, message),
+                _ => message,
+            }
+        }
+        None => message,
+    }
+}
 
 #[derive(Debug)]
 pub struct LuauTemplate {
@@ -37,66 +173,208 @@ impl UserData for LuauTemplate {
     }
 }
 
-#[derive(Debug, Copy, Clone)]
-pub struct LuauShell;
+const COLOR_ENV_VARS: [(&str, &str); 4] = [
+    ("CLICOLOR_FORCE", "1"),
+    ("CLICOLOR", "1"),
+    ("COLORTERM", "truecolor"),
+    ("TERM", "xterm-256color"),
+];
+
+/// Runs shell commands. Streamed output of `shell:run` is reported through the generator observers.
+#[derive(Clone, Default)]
+pub struct LuauShell {
+    pub generator: Option<DefaultGenerator>,
+}
 
 impl LuauShell {
-    fn exec(workdir: &String, command: &String) -> AppResult<String> {
-        let mut env_vars = HashMap::new();
-        env_vars.insert("CLICOLOR_FORCE".to_string(), "1".to_string());
-        env_vars.insert("CLICOLOR".to_string(), "1".to_string());
-        env_vars.insert("COLORTERM".to_string(), "truecolor".to_string());
-        env_vars.insert("TERM".to_string(), "xterm-256color".to_string());
-
-        let mut options = ScriptOptions::new();
-        options.working_directory = Some(PathBuf::from(workdir).canonicalize()?);
-        options.env_vars = Some(env_vars);
-
-        let mut script = run_script::spawn_script!(command, options)?;
-
-        let stdout = script.stdout.take().unwrap();
-        let stderr = script.stderr.take().unwrap();
-
-        let (sender, receiver) = sync_channel(1);
-        let stdout_sender = sender.clone();
-        let stderr_sender = sender.clone();
-        drop(sender);
-
-        let stdout_thread = thread::spawn(move || {
-            let stdout_buf = BufReader::new(stdout);
-            for line in stdout_buf.lines() {
-                stdout_sender.send(line.unwrap()).unwrap();
-            }
-            drop(stdout_sender);
-        });
+    pub fn with_generator(mut self, generator: DefaultGenerator) -> LuauShell {
+        self.generator = Some(generator);
+        self
+    }
 
-        let stderr_thread = thread::spawn(move || {
-            let stderr_buf = BufReader::new(stderr);
-            for line in stderr_buf.lines() {
-                stderr_sender.send(line.unwrap()).unwrap();
-            }
-            drop(stderr_sender);
-        });
+    /// Runs a command in the directory and returns its interleaved stdout and stderr. The output
+    /// is streamed like with `shell:run`, colors are requested as the output ends up in a terminal.
+    fn exec(&self, workdir: &str, command: &str) -> AppResult<String> {
+        let command = ShellCommand {
+            cmd: command.to_string(),
+            cwd: Some(workdir.to_string()),
+            env: None,
+            stdin: None,
+            timeout: None,
+            check: None,
+            stream: Some(true),
+        };
+        let (_, combined) = self.execute(&command)?;
+        Ok(combined)
+    }
+
+    pub fn run(&self, command: &ShellCommand) -> AppResult<ShellOutput> {
+        let (output, _) = self.execute(command)?;
+        Ok(output)
+    }
+
+    /// Runs the command, returning its output along with stdout and stderr interleaved as printed.
+    fn execute(&self, command: &ShellCommand) -> AppResult<(ShellOutput, String)> {
+        let stream = command.stream.unwrap_or(false);
+        let mut process = shell_process(&command.cmd);
+        if let Some(cwd) = &command.cwd {
+            process.current_dir(PathBuf::from(cwd).canonicalize()
+                .map_err(|e| Error::InvalidPath { path: cwd.clone(), reason: format!("Invalid working directory. {}", e) })?);
+        }
+        if stream {
+            // Output is piped, so tools should be told explicitly that it ends up in a terminal
+            process.envs(COLOR_ENV_VARS);
+        }
+        if let Some(env) = &command.env {
+            process.envs(env);
+        }
+        process
+            .stdin(match command.stdin {
+                Some(_) => Stdio::piped(),
+                None => Stdio::null(),
+            })
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+
+        let mut child = process.spawn()
+            .map_err(|e| Error::Command { command: command.cmd.clone(), message: e.to_string() })?;
+
+        if let (Some(input), Some(mut stdin)) = (command.stdin.clone(), child.stdin.take()) {
+            thread::spawn(move || stdin.write_all(input.as_bytes()));
+        }
 
-        let mut output = String::new();
-        while let Ok(line) = receiver.recv() {
-            output.push_str(line.as_str());
-            output.push('\n');
+        if stream {
+            self.notify(Start(format!("Running {}...", &command.cmd)));
         }
 
-        stderr_thread.join().unwrap();
-        stdout_thread.join().unwrap();
+        let (sender, receiver) = channel::<(bool, Vec<u8>)>();
+        read_lines_in_background(child.stdout.take().unwrap(), false, sender.clone());
+        read_lines_in_background(child.stderr.take().unwrap(), true, sender);
 
-        script.wait().unwrap();
+        let deadline = command.timeout.map(|seconds| Instant::now() + Duration::from_secs_f64(seconds));
+        let mut stdout: Vec<u8> = vec![];
+        let mut stderr: Vec<u8> = vec![];
+        let mut combined: Vec<u8> = vec![];
+        // Lines are consumed until both streams are closed, then the exit status is collected
+        loop {
+            match receiver.recv_timeout(Duration::from_millis(10)) {
+                Ok((is_stderr, line)) => {
+                    if stream {
+                        let text = String::from_utf8_lossy(&line);
+                        self.notify(Output(text.trim_end_matches(['\r', '\n']).to_string()));
+                    }
+                    combined.extend(&line);
+                    match is_stderr {
+                        true => stderr.extend(line),
+                        false => stdout.extend(line),
+                    }
+                }
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => break,
+            }
+            if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                let _ = child.kill();
+                let _ = child.wait();
+                let error = Error::CommandTimeout {
+                    command: command.cmd.clone(),
+                    seconds: command.timeout.unwrap_or_default(),
+                };
+                if stream {
+                    self.notify(Failure(error.to_string()));
+                }
+                return Err(error);
+            }
+        }
+        let status = child.wait()?;
 
-        return Ok(output)
+        let output = ShellOutput {
+            code: status.code(),
+            stdout: String::from_utf8_lossy(&stdout).to_string(),
+            stderr: String::from_utf8_lossy(&stderr).to_string(),
+        };
+        let code = output.code.map(|code| code.to_string()).unwrap_or("unknown".to_string());
+
+        if stream {
+            self.notify(match status.success() {
+                true => Finish(format!("Finished command: {}", &command.cmd)),
+                false => Failure(format!("Command exited with code {}: {}", code, &command.cmd)),
+            });
+        }
+
+        match (command.check.unwrap_or(false), status.success()) {
+            (true, false) => Err(Error::CommandFailed {
+                command: command.cmd.clone(),
+                code: output.code,
+                stderr: output.stderr,
+            }),
+            _ => Ok((output, String::from_utf8_lossy(&combined).to_string())),
+        }
+    }
+
+    fn notify(&self, event: GeneratorEvent) {
+        if let Some(generator) = &self.generator {
+            generator.notify(event);
+        }
     }
 }
 
+/// Options of `shell:run`. The command is interpreted by the system shell, `env` is added on top
+/// of the inherited environment and `timeout` is expressed in seconds. With `stream` every line
+/// is forwarded to the generator observers as soon as it is printed, and still captured.
+#[derive(Deserialize)]
+pub struct ShellCommand {
+    pub cmd: String,
+    pub cwd: Option<String>,
+    pub env: Option<BTreeMap<String, String>>,
+    pub stdin: Option<String>,
+    pub timeout: Option<f64>,
+    pub check: Option<bool>,
+    pub stream: Option<bool>,
+}
+
+#[derive(Serialize)]
+pub struct ShellOutput {
+    pub code: Option<i32>,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+#[cfg(unix)]
+fn shell_process(command: &str) -> Command {
+    let mut process = Command::new("sh");
+    process.arg("-c").arg(command);
+    process
+}
+
+#[cfg(not(unix))]
+fn shell_process(command: &str) -> Command {
+    let mut process = Command::new("cmd");
+    process.arg("/C").arg(command);
+    process
+}
+
+fn read_lines_in_background<R: Read + Send + 'static>(reader: R, is_stderr: bool, sender: Sender<(bool, Vec<u8>)>) {
+    thread::spawn(move || {
+        let mut reader = BufReader::new(reader);
+        loop {
+            let mut line = vec![];
+            match reader.read_until(b'\n', &mut line) {
+                Ok(0) | Err(_) => break,
+                Ok(_) => {
+                    if sender.send((is_stderr, line)).is_err() {
+                        break;
+                    }
+                }
+            }
+        }
+    });
+}
+
 impl UserData for LuauShell {
     fn add_methods<'lua, M: UserDataMethods<'lua, Self>>(methods: &mut M) {
-        methods.add_function("exec", |_, (_ud, path, command): (AnyUserData, String, String)| {
-            match Self::exec(&path, &command) {
+        methods.add_function("exec", |lua, (ud, path, command): (AnyUserData, String, String)| {
+            let shell = ud.borrow::<LuauShell>()?;
+            match excluding_time(lua, || shell.exec(&path, &command)) {
                 Ok(stdout_string) => {
                     let encoded = stdout_string
                         .chars()
@@ -124,6 +402,460 @@ impl UserData for LuauShell {
                 Err(e) => Err(RuntimeError(format!("Cannot evaluate script. {}", e).to_string()))
             }
         });
+
+        methods.add_function("run", |lua, (ud, command): (AnyUserData, mlua::Value)| {
+            let command: ShellCommand = lua.from_value(command)?;
+            let shell = ud.borrow::<LuauShell>()?;
+            let output = excluding_time(lua, || shell.run(&command)).map_err(|e| RuntimeError(e.to_string()))?;
+            lua.to_value(&output)
+        });
+    }
+}
+
+/// Stands in for a module the script has no capability for; any use of it fails with a
+/// message naming the missing capability.
+#[derive(Debug, Clone)]
+pub struct LuauDenied {
+    pub message: String,
+}
+
+impl UserData for LuauDenied {
+    fn add_methods<'lua, M: UserDataMethods<'lua, Self>>(methods: &mut M) {
+        methods.add_meta_method(MetaMethod::Index, |_, denied, _key: mlua::Value| -> mlua::Result<()> {
+            Err(RuntimeError(denied.message.clone()))
+        });
+    }
+}
+
+#[derive(Serialize)]
+struct FsStat {
+    kind: String,
+    size: u64,
+    modified: Option<u64>,
+    readonly: bool,
+    mode: Option<u32>,
+}
+
+/// Access to the filesystem for Luau scripts. Every path is resolved against the project
+/// directory and should stay inside of it or inside one of the registered target directories.
+/// Write operations are only available when a writer is attached, which is the case for actions,
+/// and when the sandbox of the script grants the fs-write capability.
+#[derive(Clone)]
+pub struct LuauFs {
+    pub roots: Vec<PathBuf>,
+    pub writer: Option<LuauFsWriter>,
+    pub sandbox: Option<Sandbox>,
+}
+
+/// Reports write operations through the generator observers and skips them on a dry run.
+#[derive(Clone)]
+pub struct LuauFsWriter {
+    pub generator: DefaultGenerator,
+    pub dry_run: bool,
+}
+
+#[derive(Deserialize, Default)]
+struct FsWriteOptions {
+    mode: Option<String>,
+    overwrite: Option<bool>,
+}
+
+impl LuauFs {
+    pub fn new(context: &ResolvedContext) -> AppResult<LuauFs> {
+        let base_dir = context.directory_path.canonicalize()?;
+        let mut fs = LuauFs { roots: vec![base_dir], writer: None, sandbox: None };
+        for target in &context.current_config.targets {
+            // Targets which do not exist yet are only normalized, they may never be used
+            let target_path = resolve_target_path(&target.path).unwrap_or_else(|_| PathBuf::from(&target.path));
+            fs = fs.with_root(&target_path);
+        }
+        Ok(fs)
+    }
+
+    pub fn with_root(mut self, path: &PathBuf) -> LuauFs {
+        let root = normalize_path(&self.roots[0].join(path));
+        self.roots.push(canonicalize_nearest(&root).unwrap_or(root));
+        self
+    }
+
+    pub fn with_writer(mut self, generator: DefaultGenerator, dry_run: bool) -> LuauFs {
+        self.writer = Some(LuauFsWriter { generator, dry_run });
+        self
+    }
+
+    pub fn resolve(&self, path: &str) -> AppResult<PathBuf> {
+        let candidate = normalize_path(&self.roots[0].join(path));
+        let resolved = canonicalize_nearest(&candidate).ok_or_else(|| Error::InvalidPath {
+            path: path.to_string(),
+            reason: "Could not resolve the path, it may be a broken symbolic link.".to_string(),
+        })?;
+        match self.roots.iter().any(|root| resolved.starts_with(root)) {
+            true => Ok(resolved),
+            false => Err(Error::InvalidPath {
+                path: path.to_string(),
+                reason: "Could not leave the project and target directories.".to_string(),
+            }),
+        }
+    }
+
+    fn read(&self, path: &str) -> AppResult<String> {
+        let resolved = self.resolve(path)?;
+        let content = fs::read_to_string(&resolved)
+            .map_err(|e| file_error(path, "read from", e))?;
+        Ok(content)
+    }
+
+    fn exists(&self, path: &str) -> AppResult<bool> {
+        Ok(self.resolve(path)?.exists())
+    }
+
+    fn list(&self, path: &str) -> AppResult<Vec<String>> {
+        let resolved = self.resolve(path)?;
+        let mut names: Vec<String> = vec![];
+        for entry in fs::read_dir(&resolved).map_err(|e| file_error(path, "list", e))? {
+            names.push(entry?.file_name().to_string_lossy().to_string());
+        }
+        names.sort();
+        Ok(names)
+    }
+
+    /// Matches the pattern against the project directory, matched paths are relative to it.
+    fn glob(&self, pattern: &str) -> AppResult<Vec<String>> {
+        let invalid = |message: &str| Error::InvalidPattern { pattern: pattern.to_string(), message: message.to_string() };
+        let relative = RelativePath::new(pattern);
+        if Path::new(pattern).has_root() || relative.components().any(|c| c == relative_path::Component::ParentDir) {
+            return Err(invalid("Patterns should be relative to the project directory, without `..`."));
+        }
+        let root = &self.roots[0];
+        let anchored = format!("{}/{}", Pattern::escape(&root.to_string_lossy().replace('\\', "/")), pattern);
+        let mut paths: Vec<String> = vec![];
+        for entry in glob(&anchored).map_err(|e| invalid(&e.to_string()))? {
+            let entry_path = entry.map_err(|e| Error::File { path: e.path().to_path_buf(), operation: "read from", cause: e.into() })?;
+            let Ok(relative_path) = entry_path.strip_prefix(root) else {
+                continue;
+            };
+            let entry_string = relative_path.to_string_lossy().replace('\\', "/");
+            if self.resolve(&entry_string).is_ok() {
+                paths.push(entry_string);
+            }
+        }
+        Ok(paths)
+    }
+
+    fn stat(&self, path: &str) -> AppResult<FsStat> {
+        let resolved = self.resolve(path)?;
+        let metadata = fs::symlink_metadata(&resolved)
+            .map_err(|e| file_error(path, "stat", e))?;
+        let kind = match (metadata.is_dir(), metadata.is_symlink()) {
+            (true, _) => "dir",
+            (_, true) => "symlink",
+            _ => "file",
+        };
+        let modified = metadata.modified().ok()
+            .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+            .map(|duration| duration.as_secs());
+        Ok(FsStat {
+            kind: kind.to_string(),
+            size: metadata.len(),
+            modified,
+            readonly: metadata.permissions().readonly(),
+            mode: load_file_mode(&resolved),
+        })
+    }
+
+    fn walk(&self, path: &str) -> AppResult<Vec<String>> {
+        let resolved = self.resolve(path)?;
+        let prefix = match RelativePath::new(path).normalize().as_str() {
+            "" => String::new(),
+            normalized => format!("{}/", normalized),
+        };
+        let mut paths: Vec<String> = vec![];
+        walk_directory(&resolved, &prefix, &mut paths)
+            .map_err(|cause| file_error(path, "walk", cause))?;
+        paths.sort();
+        Ok(paths)
+    }
+
+    /// Fails for the repository metadata and the configuration files, which are not touched by
+    /// write operations, nor are the directories directly containing them.
+    fn ensure_unprotected(&self, path: &str, resolved: &Path) -> AppResult<()> {
+        let inner = self.roots.iter()
+            .find_map(|root| resolved.strip_prefix(root).ok())
+            .unwrap_or(resolved);
+        let protected = inner.components().any(|component| is_protected_name(component.as_os_str()))
+            || (resolved.is_dir() && PROTECTED_NAMES.iter().any(|name| resolved.join(name).exists()));
+        match protected {
+            true => Err(Error::InvalidPath {
+                path: path.to_string(),
+                reason: format!("Paths containing {} cannot be changed.", PROTECTED_NAMES.join(" or ")),
+            }),
+            false => Ok(()),
+        }
+    }
+
+    fn writer(&self, operation: &str) -> AppResult<&LuauFsWriter> {
+        ensure_writable(&self.sandbox, "writing files")?;
+        self.writer.as_ref().ok_or_else(|| Error::WriteNotAllowed { operation: operation.to_string() })
+    }
+
+    /// Notifies the observers and tells whether the operation should actually be performed.
+    fn report(&self, operation: &str, description: &str, path: &str) -> AppResult<bool> {
+        let writer = self.writer(operation)?;
+        let message = format!("{}: {}", description, path);
+        writer.generator.notify(match writer.dry_run {
+            true => Skip(message),
+            false => Finish(message),
+        });
+        Ok(!writer.dry_run)
+    }
+
+    fn mkdir(&self, path: &str) -> AppResult<()> {
+        let resolved = self.resolve(path)?;
+        // Existing directories are left as they are, even when they hold protected entries
+        if resolved.is_dir() {
+            return Ok(());
+        }
+        self.ensure_unprotected(path, &resolved)?;
+        if self.report("mkdir", "Created directory", path)? {
+            fs::create_dir_all(&resolved)
+                .map_err(|e| file_error(path, "create directory", e))?;
+        }
+        Ok(())
+    }
+
+    fn write(&self, path: &str, content: &str, options: FsWriteOptions) -> AppResult<()> {
+        let resolved = self.resolve(path)?;
+        self.ensure_unprotected(path, &resolved)?;
+        let mode = options.mode.as_deref().map(parse_file_mode).transpose()?;
+        if !options.overwrite.unwrap_or(true) && resolved.exists() {
+            return Ok(());
+        }
+        if self.report("write", "Wrote file", path)? {
+            create_parent_directory(&resolved)?;
+            fs::write(&resolved, content)
+                .map_err(|e| file_error(path, "write to", e))?;
+            if let Some(mode) = mode {
+                save_file_mode(&resolved, mode)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn copy(&self, from: &str, to: &str) -> AppResult<()> {
+        let source = self.resolve(from)?;
+        let destination = self.resolve(to)?;
+        self.ensure_unprotected(to, &destination)?;
+        if !source.exists() {
+            return Err(file_error(from, "copy", io::ErrorKind::NotFound.into()));
+        }
+        if destination.starts_with(&source) {
+            return Err(Error::InvalidPath {
+                path: to.to_string(),
+                reason: format!("Could not copy {} into itself.", from),
+            });
+        }
+        if self.report("copy", &format!("Copied {} to", from), to)? {
+            copy_path(&source, &destination).map_err(|e| match e {
+                Error::Io(cause) => file_error(from, "copy", cause),
+                e => e,
+            })?;
+        }
+        Ok(())
+    }
+
+    fn rename(&self, from: &str, to: &str) -> AppResult<()> {
+        let source = self.resolve(from)?;
+        let destination = self.resolve(to)?;
+        self.ensure_unprotected(from, &source)?;
+        self.ensure_unprotected(to, &destination)?;
+        if !source.exists() {
+            return Err(file_error(from, "move", io::ErrorKind::NotFound.into()));
+        }
+        if self.report("move", &format!("Moved {} to", from), to)? {
+            create_parent_directory(&destination)?;
+            fs::rename(&source, &destination)
+                .map_err(|e| file_error(from, "move", e))?;
+        }
+        Ok(())
+    }
+
+    fn remove(&self, path: &str) -> AppResult<()> {
+        let resolved = self.resolve(path)?;
+        if self.roots.contains(&resolved) {
+            return Err(Error::InvalidPath {
+                path: path.to_string(),
+                reason: "Project and target directories cannot be removed.".to_string(),
+            });
+        }
+        self.ensure_unprotected(path, &resolved)?;
+        if !resolved.exists() {
+            return Ok(());
+        }
+        if self.report("remove", "Removed", path)? {
+            let result = match resolved.is_dir() {
+                true => fs::remove_dir_all(&resolved),
+                false => fs::remove_file(&resolved),
+            };
+            result.map_err(|e| file_error(path, "remove", e))?;
+        }
+        Ok(())
+    }
+}
+
+/// Fails with the missing capability when the sandbox of the script does not grant fs-write.
+fn ensure_writable(sandbox: &Option<Sandbox>, operation: &str) -> AppResult<()> {
+    match sandbox {
+        Some(sandbox) if !sandbox.allows(Capability::FsWrite) => Err(sandbox.missing(Capability::FsWrite, operation)),
+        _ => Ok(()),
+    }
+}
+
+/// Entries write operations of scripts keep away from.
+const PROTECTED_NAMES: [&str; 2] = [".git", DEFAULT_CONFIG_NAME];
+
+fn is_protected_name(name: &OsStr) -> bool {
+    PROTECTED_NAMES.iter().any(|protected| name == *protected)
+}
+
+fn file_error(path: &str, operation: &'static str, cause: io::Error) -> Error {
+    Error::File { path: PathBuf::from(path), operation, cause }
+}
+
+fn create_parent_directory(path: &Path) -> AppResult<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    Ok(())
+}
+
+/// Copies a file or a directory tree. Symbolic links are not followed, as they may point
+/// outside of the project, and protected entries are not written.
+fn copy_path(source: &Path, destination: &Path) -> AppResult<()> {
+    let metadata = fs::symlink_metadata(source)?;
+    if metadata.is_symlink() {
+        return Err(Error::InvalidPath {
+            path: source.display().to_string(),
+            reason: "Symbolic links cannot be copied.".to_string(),
+        });
+    }
+    if destination.file_name().is_some_and(is_protected_name) {
+        return Err(Error::InvalidPath {
+            path: destination.display().to_string(),
+            reason: format!("Paths containing {} cannot be changed.", PROTECTED_NAMES.join(" or ")),
+        });
+    }
+    match metadata.is_dir() {
+        true => {
+            fs::create_dir_all(destination)?;
+            for entry in fs::read_dir(source)? {
+                let entry = entry?;
+                copy_path(&entry.path(), &destination.join(entry.file_name()))?;
+            }
+        }
+        false => {
+            create_parent_directory(destination)?;
+            fs::copy(source, destination)?;
+        }
+    }
+    Ok(())
+}
+
+/// Canonicalizes the nearest existing ancestor of the path and appends the missing components,
+/// so symbolic links are followed for paths which do not exist yet. Broken links are not resolved.
+fn canonicalize_nearest(path: &Path) -> Option<PathBuf> {
+    let mut missing = vec![];
+    let mut current = path;
+    loop {
+        match current.canonicalize() {
+            Ok(existing) => return Some(missing.iter().rev().fold(existing, |resolved, name| resolved.join(name))),
+            Err(_) if fs::symlink_metadata(current).is_ok() => return None,
+            Err(_) => {
+                missing.push(current.file_name()?);
+                current = current.parent()?;
+            }
+        }
+    }
+}
+
+fn walk_directory(directory: &PathBuf, prefix: &String, paths: &mut Vec<String>) -> io::Result<()> {
+    for entry in fs::read_dir(directory)? {
+        let entry = entry?;
+        let name = format!("{}{}", prefix, entry.file_name().to_string_lossy());
+        match entry.file_type()?.is_dir() {
+            true => walk_directory(&entry.path(), &format!("{}/", name), paths)?,
+            false => paths.push(name),
+        }
+    }
+    Ok(())
+}
+
+fn normalize_path(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            Component::CurDir => {}
+            other => normalized.push(other.as_os_str()),
+        }
+    }
+    normalized
+}
+
+impl UserData for LuauFs {
+    fn add_methods<'lua, M: UserDataMethods<'lua, Self>>(methods: &mut M) {
+        methods.add_function("read", |_, (ud, path): (AnyUserData, String)| {
+            ud.borrow::<LuauFs>()?.read(&path).map_err(|e| RuntimeError(e.to_string()))
+        });
+
+        methods.add_function("exists", |_, (ud, path): (AnyUserData, String)| {
+            ud.borrow::<LuauFs>()?.exists(&path).map_err(|e| RuntimeError(e.to_string()))
+        });
+
+        methods.add_function("list", |lua, (ud, path): (AnyUserData, String)| {
+            let names = ud.borrow::<LuauFs>()?.list(&path).map_err(|e| RuntimeError(e.to_string()))?;
+            lua.to_value(&names)
+        });
+
+        methods.add_function("glob", |lua, (ud, pattern): (AnyUserData, String)| {
+            let paths = ud.borrow::<LuauFs>()?.glob(&pattern).map_err(|e| RuntimeError(e.to_string()))?;
+            lua.to_value(&paths)
+        });
+
+        methods.add_function("stat", |lua, (ud, path): (AnyUserData, String)| {
+            let stat = ud.borrow::<LuauFs>()?.stat(&path).map_err(|e| RuntimeError(e.to_string()))?;
+            lua.to_value(&stat)
+        });
+
+        methods.add_function("walk", |lua, (ud, path): (AnyUserData, String)| {
+            let paths = ud.borrow::<LuauFs>()?.walk(&path).map_err(|e| RuntimeError(e.to_string()))?;
+            lua.to_value(&paths)
+        });
+
+        methods.add_function("mkdir", |_, (ud, path): (AnyUserData, String)| {
+            ud.borrow::<LuauFs>()?.mkdir(&path).map_err(|e| RuntimeError(e.to_string()))
+        });
+
+        methods.add_function("write", |lua, (ud, path, content, options): (AnyUserData, String, String, mlua::Value)| {
+            let options: FsWriteOptions = match options {
+                mlua::Value::Nil => FsWriteOptions::default(),
+                value => lua.from_value(value)?,
+            };
+            ud.borrow::<LuauFs>()?.write(&path, &content, options).map_err(|e| RuntimeError(e.to_string()))
+        });
+
+        methods.add_function("copy", |_, (ud, from, to): (AnyUserData, String, String)| {
+            ud.borrow::<LuauFs>()?.copy(&from, &to).map_err(|e| RuntimeError(e.to_string()))
+        });
+
+        methods.add_function("move", |_, (ud, from, to): (AnyUserData, String, String)| {
+            ud.borrow::<LuauFs>()?.rename(&from, &to).map_err(|e| RuntimeError(e.to_string()))
+        });
+
+        methods.add_function("remove", |_, (ud, path): (AnyUserData, String)| {
+            ud.borrow::<LuauFs>()?.remove(&path).map_err(|e| RuntimeError(e.to_string()))
+        });
     }
 }
 
@@ -136,71 +868,166 @@ pub struct GenerationTarget {
 
 impl UserData for GenerationTarget {}
 
+/// Scroll as listed by `engine:scrolls()`.
+#[derive(Serialize)]
+pub struct EngineScroll {
+    pub name: String,
+    pub path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub about: Option<String>,
+    /// Repository which publishes the scroll, missing for scrolls of the project
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub repository: Option<String>,
+}
+
 #[derive(Clone)]
 pub struct LuauEngine {
     pub context: ResolvedContext,
     pub generator: DefaultGenerator,
+    pub dry_run: bool,
+    /// Runner of the current invocation, shared by the actions run from scripts
+    pub runner: ActionRunner,
+    /// Action which runs the script, `@/` names given to `run` refer to its package
+    pub action: Option<ActionRef>,
+    /// Sandbox of the script, generating files and running actions of other packages require fs-write
+    pub sandbox: Option<Sandbox>,
 }
 
 impl LuauEngine {
-    pub fn generate(&self, scroll_name: &String, target: &GenerationTarget, overrides: Option<Value>) -> AppResult<()> {
-        let resolved_target_path = resolve_target(
+    pub fn scrolls(&self) -> AppResult<Vec<EngineScroll>> {
+        let scrolls = list_scrolls(&self.context)?
+            .into_values()
+            .map(|scroll| EngineScroll {
+                path: match &scroll.dependency_name {
+                    None => scroll.scroll.path.clone(),
+                    Some(n) => RelativePathBuf::from("ggcode_modules").join(n).join(&scroll.scroll.path).to_string(),
+                },
+                name: scroll.full_name,
+                about: scroll.scroll.about,
+                repository: scroll.dependency_name,
+            })
+            .collect();
+        Ok(scrolls)
+    }
+
+    pub fn targets(&self) -> Vec<TargetEntry> {
+        self.context.current_config.targets.clone()
+    }
+
+    pub fn target_path(&self, target: &GenerationTarget) -> AppResult<PathBuf> {
+        resolve_target(
             &self.context,
             target.target_name.clone(),
-            target.target_path.clone())?;
+            target.target_path.clone())
+    }
+
+    pub fn variables(&self, scroll_name: &String, target: Option<&GenerationTarget>) -> AppResult<Value> {
+        let target_path = target.map(|t| self.target_path(t)).transpose()?;
+        self.generator.load_scroll_variables(scroll_name, target_path.as_ref())
+    }
+
+    pub fn render(&self, scroll_name: &String, template_name: &String, target: Option<&GenerationTarget>, overrides: Option<Value>) -> AppResult<String> {
+        let target_path = target.map(|t| self.target_path(t)).transpose()?;
+        self.generator.render_template(scroll_name, template_name, target_path.as_ref(), overrides)
+    }
+
+    /// Generates the files of a scroll, returning the paths of the written files.
+    pub fn generate(&self, scroll_name: &String, target: &GenerationTarget, overrides: Option<Value>) -> AppResult<Vec<PathBuf>> {
+        let resolved_target_path = self.target_path(target)?;
+        let dry_run = self.dry_run || target.dry_run.unwrap_or(false);
+        if !dry_run {
+            ensure_writable(&self.sandbox, "generating files")?;
+        }
 
         self.generator.generate(
             scroll_name,
             &resolved_target_path,
-            target.dry_run.unwrap_or(false),
-            overrides)?;
+            dry_run,
+            overrides)
+    }
 
-        Ok(())
+    pub fn run(&self, action_name: &str, args: Option<Value>) -> AppResult<()> {
+        let name = match &self.action {
+            Some(action) => action.resolve_name(action_name),
+            None => action_name.to_string(),
+        };
+        // Actions of the same repository run in the same sandbox, other ones could write for it
+        if let Some(sandbox) = &self.sandbox {
+            let action = find_action_by_full_name(&self.context, &name)?;
+            if action.dependency_name.as_ref() != Some(&sandbox.repository) {
+                ensure_writable(&self.sandbox, "running actions of other packages")?;
+            }
+        }
+        self.runner.run(&name, &args.unwrap_or(Value::Null))
+    }
+}
+
+/// Reads the variables passed to the engine, `nil` and empty tables override nothing.
+fn read_overrides(lua: &Lua, value: mlua::Value) -> Option<Value> {
+    match lua.from_value::<Value>(value).ok()? {
+        Value::Null => None,
+        Value::Sequence(values) if values.is_empty() => None,
+        value => Some(value),
     }
 }
 
 impl UserData for LuauEngine {
     fn add_methods<'lua, M: UserDataMethods<'lua, Self>>(methods: &mut M) {
+        methods.add_function("scrolls", |lua, ud: AnyUserData| {
+            let scrolls = ud.borrow::<LuauEngine>()?.scrolls().map_err(|e| RuntimeError(e.to_string()))?;
+            lua.to_value(&scrolls)
+        });
+
+        methods.add_function("targets", |lua, ud: AnyUserData| {
+            lua.to_value(&ud.borrow::<LuauEngine>()?.targets())
+        });
+
+        methods.add_function("target_path", |lua, (ud, target): (AnyUserData, mlua::Value)| {
+            let target_object: GenerationTarget = lua.from_value(target)?;
+
+            ud.borrow::<LuauEngine>()?.target_path(&target_object)
+                .map(|path| path.to_string_lossy().to_string())
+                .map_err(|e| RuntimeError(e.to_string()))
+        });
+
+        methods.add_function("variables", |lua, (ud, scroll, target): (AnyUserData, String, mlua::Value)| {
+            let target_object: Option<GenerationTarget> = lua.from_value(target)?;
+
+            let variables = ud.borrow::<LuauEngine>()?.variables(&scroll, target_object.as_ref())
+                .map_err(|e| RuntimeError(format!("Cannot load variables of scroll: {}. {}", scroll, e)))?;
+            lua.to_value(&variables)
+        });
+
+        methods.add_function("render", |lua, (ud, scroll, template, target, variables): (AnyUserData, String, String, mlua::Value, mlua::Value)| {
+            let variables_yaml = read_overrides(lua, variables);
+            let target_object: Option<GenerationTarget> = lua.from_value(target)?;
+
+            ud.borrow::<LuauEngine>()?.render(&scroll, &template, target_object.as_ref(), variables_yaml)
+                .map_err(|e| RuntimeError(format!("Cannot render template: {} of scroll: {}. {}", template, scroll, e)))
+        });
+
         methods.add_function("generate", |lua, (ud, scroll, target, variables): (AnyUserData, String, mlua::Value, mlua::Value)| {
-            let variables_yaml: Option<Value> = lua.from_value(variables).ok();
+            let variables_yaml = read_overrides(lua, variables);
             let target_object: GenerationTarget = lua.from_value(target)?;
 
             let engine = ud.borrow::<LuauEngine>()?;
 
-            match engine.generate(&scroll, &target_object, variables_yaml) {
-                Ok(()) => {},
+            let paths = match engine.generate(&scroll, &target_object, variables_yaml) {
+                Ok(paths) => paths,
                 Err(e) => return Err(RuntimeError(format!("Cannot generate using scroll: {}. {}", scroll, e).to_string())),
             };
-            Ok(())
+            let paths: Vec<String> = paths.iter().map(|path| path.to_string_lossy().to_string()).collect();
+            lua.to_value(&paths)
         });
-    }
-}
 
-pub fn trace_mlua_error(script: &String, e: &Box<dyn Error>) {
-    let description = describe_error(&e);
-    match description {
-        Ok(Some(ErrorDescription::SourceError(data))) => {
-            let source = match data.is_pointed {
-                true => {
-                    fs::read_to_string(data.location).unwrap()
-                },
-                false => script.clone(),
-            };
-            let vec: Vec<&str> = source.lines().collect();
-            let mut area_vec = vec![];
-            let lower = cmp::max(0, data.line as i32 - 1) as usize;
-            let upper = cmp::min(data.line + 1, vec.len() - 1) + 1;
-            for i in lower..upper {
-                let row = format!("{: >6} │ {}", format!("L{}", i + 1), vec[i]);
-                let styled = match i == data.line {
-                    true => format!("{: <80}", style(row).white().bg(Color::Color256(52))),
-                    false => format!("{: <80}", style(row).white().bg(Color::Color256(17))),
-                };
-                area_vec.push(styled.to_string());
-            }
-            let area_st = area_vec.join("\n");
-            eprintln!("\n{}\n{}\n", style(data.details).bold(), area_st)
-        }
-        _ => {}
+        methods.add_function("run", |lua, (ud, name, args): (AnyUserData, String, mlua::Value)| {
+            let args_yaml: Option<Value> = lua.from_value(args)?;
+
+            let engine = ud.borrow::<LuauEngine>()?;
+
+            engine.run(&name, args_yaml)
+                .map_err(|e| RuntimeError(format!("Cannot run action: {}. {}", name, e)))
+        });
     }
 }
+
@@ -1,82 +1,92 @@
-use std::collections::BTreeMap;
-use std::error::Error;
-use std::fs;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
 
-use mlua::{Lua, LuaSerdeExt};
-use serde_yaml::Value;
+use mlua::{Compiler, Lua, LuaSerdeExt, RegistryKey, Table};
+use relative_path::RelativePath;
 
+use crate::luau::luau_case::LuauCase;
+use crate::luau::luau_dotenv::LuauDotenv;
+use crate::luau::luau_ini::LuauIni;
 use crate::luau::luau_json::LuauJson;
+use crate::luau::luau_str::LuauStr;
+use crate::luau::luau_toml::LuauToml;
 use crate::luau::luau_uuid::LuauUuid;
 use crate::luau::luau_yaml::LuauYaml;
 use crate::renderer::builder::RendererBuilder;
-use crate::renderer::luau_extras::{LuauTemplate, trace_mlua_error};
-use crate::types::ErrorBox;
-
+use crate::error::{Error, ScriptKind};
+use crate::renderer::luau_extras::{LuauLimits, LuauTemplate};
+use crate::storage::load_template_string;
+use crate::types::AppResult;
+
+/// Renders `.luau` templates with a single prepared Luau state per scroll. Variables and modules
+/// are converted once into a shared globals table, while every template and path expression is
+/// executed in its own environment that falls back to the shared one. The shared tables, nested
+/// variables and the standard library are read-only, so templates cannot change each other's state.
 #[derive(Debug)]
 pub struct LuaRenderer {
-    values: BTreeMap<String, Value>,
+    lua: Lua,
+    shared_globals: RegistryKey,
     templates: BTreeMap<String, String>,
+    expressions: RefCell<HashMap<String, Vec<u8>>>,
+    limits: LuauLimits,
 }
 
 impl LuaRenderer {
-    pub fn render<N: Into<String>>(&self, name: N) -> Result<String, Box<dyn Error>> {
+    pub fn render<N: Into<String>>(&self, name: N) -> AppResult<String> {
         let name_string = &name.into();
-        let lua = Lua::new();
-
-        let globals = lua.globals();
 
-        for (key, value) in &self.values {
-            let lua_value = lua.to_value(value)?;
-            globals.set(key.as_str(), lua_value)?;
-        }
+        let script = self.templates
+            .get(name_string)
+            .ok_or_else(|| Error::TemplateNotFound { name: name_string.clone() })?;
 
-        let template = lua.create_userdata(LuauTemplate {
+        let template = self.lua.create_userdata(LuauTemplate {
             st: String::new()
         })?;
 
-        globals.set("template", &template)?;
-
-        globals.set("null", lua.null())?;
-        globals.set("array_mt", lua.array_metatable())?;
-        globals.set("yaml", &lua.create_userdata(LuauYaml)?)?;
-        globals.set("json", &lua.create_userdata(LuauJson)?)?;
-        globals.set("uuid", lua.create_userdata(LuauUuid)?)?;
-
+        let environment = self.create_environment()?;
+        environment.raw_set("template", &template)?;
 
-        let script = self.templates
-            .get(name_string)
-            .ok_or::<Box<dyn Error>>(format!("No template: {}", name_string).into())?;
+        self.limits.apply(&self.lua)?;
 
-        lua.load(script).exec()
-            .map_err::<ErrorBox, _>(|e| {
-                trace_mlua_error(script, &e.clone().into());
-                format!("Error parsing template: {e}").into()
-            })?;
+        self.lua.load(script)
+            .set_name(name_string)
+            .set_environment(environment)
+            .exec()
+            .map_err(|e| self.limits.script_error(ScriptKind::Template, name_string, script, e))?;
 
         let result = template.borrow::<LuauTemplate>()?.st.clone();
 
         Ok(result)
     }
 
-    pub fn eval_string_template<S: Into<String>>(&self, raw: S) -> Result<String, Box<dyn Error>> {
-        let lua = Lua::new();
-
-        let globals = lua.globals();
+    pub fn eval_string_template<S: Into<String>>(&self, raw: S) -> AppResult<String> {
+        let script = raw.into();
 
-        for (key, value) in &self.values {
-            let lua_value = lua.to_value(value)?;
-            globals.set(key.as_str(), lua_value)?;
-        }
+        let mut expressions = self.expressions.borrow_mut();
+        let bytecode = expressions
+            .entry(script.clone())
+            .or_insert_with(|| Compiler::new().compile(format!("return {}", script)));
 
-        let script = raw.into();
+        self.limits.apply(&self.lua)?;
 
-        let res = lua.load(script.as_str()).eval::<String>()?;
+        let res = self.lua.load(&bytecode[..])
+            .set_environment(self.create_environment()?)
+            .eval::<String>()?;
         Ok(res)
     }
+
+    fn create_environment(&self) -> mlua::Result<Table<'_>> {
+        let shared_globals: Table = self.lua.registry_value(&self.shared_globals)?;
+        let metatable = self.lua.create_table()?;
+        metatable.raw_set("__index", shared_globals)?;
+        let environment = self.lua.create_table()?;
+        environment.set_metatable(Some(metatable));
+        Ok(environment)
+    }
 }
 
 impl RendererBuilder {
-    pub fn build_lua(&self) -> Result<LuaRenderer, Box<dyn Error>> {
+    pub fn build_lua(&self) -> AppResult<LuaRenderer> {
         let mut templates: BTreeMap<String, String> = BTreeMap::new();
 
         for (key, value) in &self.raw_templates {
@@ -84,30 +94,72 @@ impl RendererBuilder {
         }
 
         for (key, path) in &self.file_templates {
-            let raw = fs::read_to_string(path)?;
-            templates.insert(key.clone(), raw);
+            if RelativePath::new(key).extension() == Some("luau") {
+                templates.insert(key.clone(), load_template_string(path)?);
+            }
         }
 
-        let mut values: BTreeMap<String, Value> = BTreeMap::new();
+        let lua = Lua::new();
+        if let Some(memory) = self.luau_limits.memory {
+            lua.set_memory_limit(memory)?;
+        }
+
+        let globals = lua.globals();
 
         for (key, value) in &self.values {
-            values.insert(key.clone(), value.clone());
+            let lua_value = lua.to_value(value)?;
+            globals.set(key.as_str(), lua_value)?;
         }
 
+        globals.set("null", lua.null())?;
+        globals.set("array_mt", lua.array_metatable())?;
+        globals.set("yaml", lua.create_userdata(LuauYaml)?)?;
+        globals.set("json", lua.create_userdata(LuauJson)?)?;
+        globals.set("uuid", lua.create_userdata(LuauUuid)?)?;
+        globals.set("case", lua.create_userdata(LuauCase)?)?;
+        globals.set("str", lua.create_userdata(LuauStr)?)?;
+        globals.set("toml", lua.create_userdata(LuauToml)?)?;
+        globals.set("dotenv", lua.create_userdata(LuauDotenv)?)?;
+        globals.set("ini", lua.create_userdata(LuauIni)?)?;
+
+        freeze(&globals)?;
+        let shared_globals = lua.create_registry_value(globals)?;
+        // Libraries and builtin metatables become read-only as well
+        lua.sandbox(true)?;
+
         let renderer = LuaRenderer {
-            values,
+            lua,
+            shared_globals,
             templates,
+            expressions: RefCell::new(HashMap::new()),
+            limits: self.luau_limits,
         };
 
         Ok(renderer)
     }
 }
 
+/// Makes a table and all the tables it contains read-only.
+fn freeze(table: &Table) -> mlua::Result<()> {
+    if table.is_readonly() {
+        return Ok(());
+    }
+    table.set_readonly(true);
+    for pair in table.clone().pairs::<mlua::Value, mlua::Value>() {
+        let (_, value) = pair?;
+        if let mlua::Value::Table(nested) = value {
+            freeze(&nested)?;
+        }
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use std::error::Error;
 
     use crate::renderer::builder::RendererBuilder;
+    use crate::renderer::luau_extras::LuauLimits;
     use crate::renderer::luau_renderer::LuaRenderer;
 
     #[test]
@@ -127,6 +179,47 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn luau_renderer_isolation_test() -> Result<(), Box<dyn Error>> {
+        let builder = RendererBuilder::new()
+            .with_value("foo", "one")
+            .with_value("nested", &serde_yaml::from_str::<serde_yaml::Value>("{ items: [a, b] }")?)
+            .with_raw_template("FIRST.txt", "leaked = foo\ntemplate:print(leaked)")
+            .with_raw_template("SECOND.txt", "template:print(tostring(leaked))")
+            .with_raw_template("NESTED.txt", "nested.items[1] = 'changed'")
+            .with_raw_template("STRING.txt", "string.upper = function() return 'changed' end")
+            .with_raw_template("READ.txt", "template:print(`{nested.items[1]} {string.upper('b')}`)");
+
+        let renderer: LuaRenderer = builder.build_lua()?;
+        assert_eq!(renderer.render("FIRST.txt")?, "one");
+        assert_eq!(renderer.render("SECOND.txt")?, "nil");
+        assert_eq!(renderer.render("FIRST.txt")?, "one");
+
+        assert!(renderer.render("NESTED.txt").unwrap_err().to_string().contains("readonly"));
+        assert!(renderer.render("STRING.txt").unwrap_err().to_string().contains("readonly"));
+        assert_eq!(renderer.render("READ.txt")?, "a B");
+
+        for _ in 0..3 {
+            assert_eq!(renderer.eval_string_template("`{case:snake(foo)}.txt`")?, "one.txt");
+        }
+        assert_eq!(renderer.expressions.borrow().len(), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn luau_renderer_limits_test() -> Result<(), Box<dyn Error>> {
+        let builder = RendererBuilder::new()
+            .with_luau_limits(LuauLimits { interrupts: Some(1000), ..LuauLimits::unlimited() })
+            .with_raw_template("LOOP.txt", "while true do template:print('.') end");
+
+        let renderer: LuaRenderer = builder.build_lua()?;
+        let error = renderer.render("LOOP.txt").unwrap_err();
+        assert!(error.to_string().contains("limit of 1000 function calls and loop iterations"));
+
+        Ok(())
+    }
+
     #[test]
     fn luau_renderer_eval_test() -> Result<(), Box<dyn Error>> {
         let builder = RendererBuilder::new()
@@ -1,36 +1,59 @@
 use std::collections::BTreeMap;
-use std::error::Error;
 use std::fs;
+use std::io::{Cursor, Read};
+use std::path::PathBuf;
 
+use crate::error::Error;
 use crate::renderer::builder::RendererBuilder;
+use crate::storage::load_template_string;
+use crate::types::AppResult;
 
 #[derive(Debug)]
 pub struct NoopRenderer {
     // values: BTreeMap<String, Value>,
-    templates: BTreeMap<String, String>,
+    raw_templates: BTreeMap<String, String>,
+    file_templates: BTreeMap<String, PathBuf>,
 }
 
 impl NoopRenderer {
-    pub fn render<N: Into<String>>(&self, name: N) -> Result<String, Box<dyn Error>> {
+    pub fn render<N: Into<String>>(&self, name: N) -> AppResult<String> {
         let name_string = &name.into();
-        let template = self.templates
+        if let Some(template) = self.raw_templates.get(name_string) {
+            return Ok(template.clone())
+        }
+        let path = self.file_templates
+            .get(name_string)
+            .ok_or_else(|| Error::TemplateNotFound { name: name_string.clone() })?;
+        load_template_string(path)
+    }
+
+    /// Opens the template content as a byte stream, so binary files are copied as is.
+    pub fn open<N: Into<String>>(&self, name: N) -> AppResult<Box<dyn Read>> {
+        let name_string = &name.into();
+        if let Some(template) = self.raw_templates.get(name_string) {
+            return Ok(Box::new(Cursor::new(template.clone().into_bytes())))
+        }
+        let path = self.file_templates
             .get(name_string)
-            .ok_or::<Box<dyn Error>>(format!("No template: {}", name_string).into())?;
-        Ok(template.clone())
+            .ok_or_else(|| Error::TemplateNotFound { name: name_string.clone() })?;
+        let file = fs::File::open(path)
+            .map_err(|cause| Error::File { path: path.clone(), operation: "read from", cause })?;
+        Ok(Box::new(file))
     }
 }
 
 impl RendererBuilder {
-    pub fn build_noop(&self) -> Result<NoopRenderer, Box<dyn Error>> {
-        let mut templates: BTreeMap<String, String> = BTreeMap::new();
+    pub fn build_noop(&self) -> AppResult<NoopRenderer> {
+        let mut raw_templates: BTreeMap<String, String> = BTreeMap::new();
 
         for (key, value) in &self.raw_templates {
-            templates.insert(key.clone(), value.clone());
+            raw_templates.insert(key.clone(), value.clone());
         }
 
+        let mut file_templates: BTreeMap<String, PathBuf> = BTreeMap::new();
+
         for (key, path) in &self.file_templates {
-            let raw = fs::read_to_string(path)?;
-            templates.insert(key.clone(), raw);
+            file_templates.insert(key.clone(), path.clone());
         }
 
         // let mut values: BTreeMap<String, Value> = BTreeMap::new();
@@ -41,7 +64,8 @@ impl RendererBuilder {
 
         let renderer = NoopRenderer {
             // values,
-            templates,
+            raw_templates,
+            file_templates,
         };
 
         Ok(renderer)
@@ -51,6 +75,9 @@ impl RendererBuilder {
 #[cfg(test)]
 mod tests {
     use std::error::Error;
+    use std::fs;
+    use std::io::Read;
+
     use crate::renderer::builder::RendererBuilder;
     use crate::renderer::noop_renderer::NoopRenderer;
 
@@ -70,4 +97,26 @@ mod tests {
 
         Ok(())
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn noop_renderer_binary_test() -> Result<(), Box<dyn Error>> {
+        let path = std::env::temp_dir().join(format!("ggcode-noop-{}.png", std::process::id()));
+        let bytes: Vec<u8> = vec![0x89, 0x50, 0x4e, 0x47, 0x00, 0xff, 0xfe, 0x0a];
+        fs::write(&path, &bytes)?;
+
+        let builder = RendererBuilder::new()
+            .with_file_template("logo.png", &path);
+
+        let renderer: NoopRenderer = builder.build_noop()?;
+        let mut result: Vec<u8> = vec![];
+        renderer.open("logo.png")?.read_to_end(&mut result)?;
+        assert_eq!(result, bytes);
+        assert!(renderer.render("logo.png").is_err());
+
+        builder.build_tera()?;
+        builder.build_lua()?;
+
+        fs::remove_file(&path)?;
+        Ok(())
+    }
+}
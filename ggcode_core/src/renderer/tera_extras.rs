@@ -1,10 +1,13 @@
 use std::collections::{BTreeMap, HashMap};
 
+use convert_case::Case;
 use lazy_static::lazy_static;
 use regex::{Captures, Regex};
 use serde_json::value::{to_value, Value};
 use tera::try_get_value;
 
+use crate::utils::strings::{DEFAULT_INDENT_PREFIX, DEFAULT_WRAP_WIDTH, dedent, indent, plural, singular, to_case, wrap};
+
 struct AnsiReplacement {
     code: String,
 }
@@ -64,6 +67,55 @@ pub fn format_ansi(value: &Value, _: &HashMap<String, Value>) -> tera::Result<Va
     Ok(to_value(r).unwrap())
 }
 
+pub fn case_filter(name: &'static str, case: Case) -> impl Fn(&Value, &HashMap<String, Value>) -> tera::Result<Value> + Sync + Send {
+    move |value, _| {
+        let s = try_get_value!(format!("{}_case", name), "value", String, value);
+        Ok(to_value(to_case(&s, case)).unwrap())
+    }
+}
+
+pub fn plural_filter(value: &Value, _: &HashMap<String, Value>) -> tera::Result<Value> {
+    let s = try_get_value!("plural", "value", String, value);
+    Ok(to_value(plural(&s)).unwrap())
+}
+
+pub fn singular_filter(value: &Value, _: &HashMap<String, Value>) -> tera::Result<Value> {
+    let s = try_get_value!("singular", "value", String, value);
+    Ok(to_value(singular(&s)).unwrap())
+}
+
+/// Registered as `indent_lines`, so the builtin `indent` filter of Tera keeps its own arguments.
+pub fn indent_filter(value: &Value, args: &HashMap<String, Value>) -> tera::Result<Value> {
+    let s = try_get_value!("indent_lines", "value", String, value);
+    let prefix = match args.get("prefix") {
+        Some(p) => try_get_value!("indent_lines", "prefix", String, p),
+        None => DEFAULT_INDENT_PREFIX.to_string(),
+    };
+    let first = match args.get("first") {
+        Some(f) => try_get_value!("indent_lines", "first", bool, f),
+        None => false,
+    };
+    let blank = match args.get("blank") {
+        Some(b) => try_get_value!("indent_lines", "blank", bool, b),
+        None => false,
+    };
+    Ok(to_value(indent(&s, &prefix, first, blank)).unwrap())
+}
+
+pub fn dedent_filter(value: &Value, _: &HashMap<String, Value>) -> tera::Result<Value> {
+    let s = try_get_value!("dedent", "value", String, value);
+    Ok(to_value(dedent(&s)).unwrap())
+}
+
+pub fn wrap_filter(value: &Value, args: &HashMap<String, Value>) -> tera::Result<Value> {
+    let s = try_get_value!("wrap", "value", String, value);
+    let width = match args.get("width") {
+        Some(w) => try_get_value!("wrap", "width", usize, w),
+        None => DEFAULT_WRAP_WIDTH,
+    };
+    Ok(to_value(wrap(&s, width)).unwrap())
+}
+
 fn replace_all<E>(
     re: &Regex,
     haystack: &str,
@@ -88,6 +140,7 @@ mod tests {
 
     use serde_json::{from_value, to_value};
 
+    use crate::renderer::builder::RendererBuilder;
     use crate::renderer::tera_extras::format_ansi;
 
     #[test]
@@ -107,4 +160,18 @@ mod tests {
         assert_eq!(result_string, "\\u001b[32mdev@pc\\u001b[00m");
         Ok(())
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn string_filters_test() -> Result<(), Box<dyn Error>> {
+        let renderer = RendererBuilder::new()
+            .with_value("name", "user account")
+            .with_raw_template(
+                "SAMPLE.txt",
+                "{{ name | pascal_case }} {{ name | screaming_case }} {{ name | kebab_case | plural }} {{ \"a\nb\" | indent_lines(prefix=\"  \") }}")
+            .with_raw_template("BUILTIN.txt", "{{ \"a\nb\" | indent }}")
+            .build_tera()?;
+        assert_eq!(renderer.render("SAMPLE.txt")?, "UserAccount USER_ACCOUNT user-accounts a\n  b");
+        assert_eq!(renderer.render("BUILTIN.txt")?, "a\n    b");
+        Ok(())
+    }
+}
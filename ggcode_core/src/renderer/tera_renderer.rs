@@ -1,56 +1,101 @@
-use std::error::Error;
+use std::collections::{BTreeMap, HashMap};
 
+use relative_path::RelativePath;
 use tera::{Context, Tera};
 
+use crate::config::Capability;
 use crate::renderer::builder::RendererBuilder;
-use crate::renderer::tera_extras::format_ansi;
+use crate::renderer::tera_extras::{case_filter, dedent_filter, format_ansi, indent_filter, plural_filter, singular_filter, wrap_filter};
 use crate::renderer::tera_functions::uuid_v4;
+use crate::storage::load_template_string;
+use crate::types::AppResult;
+use crate::utils::errors::describe_tera_error;
+use crate::utils::strings::CASES;
 
 #[derive(Debug)]
 pub struct TeraRenderer {
     context: Context,
     tera: Tera,
+    /// Sources of the templates, kept to point at the failing expression
+    sources: BTreeMap<String, String>,
 }
 
 impl TeraRenderer {
-    pub fn render<N: Into<String>>(&self, name: N) -> Result<String, Box<dyn Error>> {
-        match self.tera.render(name.into().as_str(), &self.context) {
-            Ok(string) => Ok(string),
-            Err(e) => {
-                return match e.source() {
-                    Some(e1) => Err(format!("{}. {}", e, e1).into()),
-                    None => Err(format!("{}", e).into()),
-                }
-            }
-        }
+    pub fn render<N: Into<String>>(&self, name: N) -> AppResult<String> {
+        let name = name.into();
+        self.tera.render(name.as_str(), &self.context).map_err(|e| {
+            let source_code = self.sources.get(&name).map(|s| s.as_str()).unwrap_or_default();
+            describe_tera_error(&name, source_code, &e, Some(&self.context))
+        })
     }
 }
 
 impl RendererBuilder {
 
-    pub fn build_tera(&self) -> Result<TeraRenderer, Box<dyn Error>> {
+    pub fn build_tera(&self) -> AppResult<TeraRenderer> {
         let mut context = Context::new();
         let mut tera = Tera::default();
 
         tera.register_filter("format_ansi", format_ansi);
 
+        for (name, case) in CASES {
+            tera.register_filter(&format!("{}_case", name), case_filter(name, case));
+        }
+
+        tera.register_filter("plural", plural_filter);
+        tera.register_filter("singular", singular_filter);
+        tera.register_filter("indent_lines", indent_filter);
+        tera.register_filter("dedent", dedent_filter);
+        tera.register_filter("wrap", wrap_filter);
+
         for (key, value) in &self.values {
             context.insert(key, &value.clone());
         }
 
+        let mut templates: Vec<(String, String)> = vec![];
+
         for (name, raw) in &self.raw_templates {
-            tera.add_raw_template(&name, &raw)?;
+            templates.push((name.clone(), raw.clone()));
         }
 
         for (name, path) in &self.file_templates {
-            tera.add_template_file(path, Some(&name))?;
+            if RelativePath::new(name).extension() == Some("tera") {
+                templates.push((name.clone(), load_template_string(path)?));
+            }
         }
 
+        // Library templates are only reachable through extends, include and import
+        for (name, path) in &self.library_templates {
+            if RelativePath::new(name).extension() == Some("tera") {
+                templates.push((name.clone(), load_template_string(path)?));
+            }
+        }
+
+        let sources: BTreeMap<String, String> = templates.into_iter().collect();
+
+        tera.add_raw_templates(sources.iter()).map_err(|e| {
+            // Syntax errors name the template which failed to parse
+            let name = sources.keys().find(|name| e.to_string() == format!("Failed to parse '{}'", name));
+            match name {
+                Some(name) => describe_tera_error(name, &sources[name], &e, None),
+                None => describe_tera_error("", "", &e, None),
+            }
+        })?;
+
         tera.register_function("uuid_v4", uuid_v4);
 
+        // The builtin `get_env` reads the environment of the process
+        if let Some(sandbox) = self.sandbox.as_ref().filter(|sandbox| !sandbox.allows(Capability::Env)) {
+            let message = sandbox.missing(Capability::Env, "environment variables").to_string();
+            tera.register_function("get_env", move |_: &HashMap<String, tera::Value>| {
+                Err(tera::Error::msg(message.clone()))
+            });
+        }
+
         let renderer = TeraRenderer {
             context,
-            tera
+            tera,
+            sources,
         };
 
         Ok(renderer)
@@ -59,8 +104,11 @@ impl RendererBuilder {
 
 #[cfg(test)]
 mod tests {
+    use std::collections::BTreeSet;
     use std::error::Error;
 
+    use crate::capability::Sandbox;
+    use crate::error::Error as AppError;
     use crate::renderer::builder::RendererBuilder;
     use crate::renderer::tera_renderer::TeraRenderer;
 
@@ -80,4 +128,83 @@ mod tests {
 
         Ok(())
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn tera_renderer_sandbox_test() -> Result<(), Box<dyn Error>> {
+        let template = "{{ get_env(name=\"PATH\", default=\"\") | length > 0 }}";
+        let trusted = RendererBuilder::new()
+            .with_raw_template("trusted.txt", template)
+            .build_tera()?;
+        assert_eq!(trusted.render("trusted.txt")?, "true");
+
+        let sandbox = Sandbox { repository: "dep".to_string(), capabilities: BTreeSet::new() };
+        let sandboxed = RendererBuilder::new()
+            .with_sandbox(Some(sandbox))
+            .with_raw_template("sandboxed.txt", template)
+            .build_tera()?;
+        let error = sandboxed.render("sandboxed.txt").unwrap_err();
+        assert!(error.to_string().contains("Missing capability: env"), "{}", error);
+
+        Ok(())
+    }
+
+    #[test]
+    fn tera_renderer_library_test() -> Result<(), Box<dyn Error>> {
+        let layout_path = std::env::temp_dir().join(format!("ggcode-layout-{}.tera", std::process::id()));
+        std::fs::write(&layout_path, "<h1>{% block title %}{% endblock %}</h1>")?;
+        let logo_path = std::env::temp_dir().join(format!("ggcode-logo-{}.png", std::process::id()));
+        std::fs::write(&logo_path, [0x89, b'P', b'N', b'G', 0xff, 0xfe, 0x00])?;
+
+        let builder = RendererBuilder::new()
+            .with_value("name", "app")
+            .with_library_template("core/layouts/base.html.tera", &layout_path)
+            .with_library_template("core/layouts/logo.png", &logo_path)
+            .with_raw_template(
+                "index.html",
+                "{% extends \"core/layouts/base.html.tera\" %}{% block title %}{{ name }}{% endblock %}");
+
+        let renderer: TeraRenderer = builder.build_tera()?;
+        let result = renderer.render("index.html")?;
+        assert_eq!(result, "<h1>app</h1>");
+
+        std::fs::remove_file(&layout_path)?;
+        std::fs::remove_file(&logo_path)?;
+        Ok(())
+    }
+
+    #[test]
+    fn tera_renderer_diagnostics_test() -> Result<(), Box<dyn Error>> {
+        let builder = RendererBuilder::new()
+            .with_value("name", "app")
+            .with_raw_template("unknown.txt", "Project:\n  {{ nmae | upper }}")
+            .with_raw_template("filter.txt", "{{ name | uper }}");
+
+        let renderer: TeraRenderer = builder.build_tera()?;
+        match renderer.render("unknown.txt").unwrap_err() {
+            AppError::Template { name, location: Some(location), suggestion, .. } => {
+                assert_eq!(name, "unknown.txt");
+                assert_eq!((location.line, location.column), (1, 5));
+                assert_eq!(suggestion, Some("name".to_string()));
+            }
+            e => panic!("Unexpected error: {}", e),
+        }
+        match renderer.render("filter.txt").unwrap_err() {
+            AppError::Template { location: Some(location), .. } => assert_eq!((location.line, location.column), (0, 8)),
+            e => panic!("Unexpected error: {}", e),
+        }
+
+        let syntax = RendererBuilder::new()
+            .with_raw_template("syntax.txt", "a\n{{ name | }}")
+            .build_tera()
+            .unwrap_err();
+        match syntax {
+            AppError::Template { name, message, location: Some(location), .. } => {
+                assert_eq!(name, "syntax.txt");
+                assert_eq!((location.line, location.column), (1, 10));
+                assert!(message.starts_with("Failed to parse 'syntax.txt'. expected"));
+            }
+            e => panic!("Unexpected error: {}", e),
+        }
+        Ok(())
+    }
+}
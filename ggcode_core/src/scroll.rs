@@ -1,9 +1,10 @@
 use std::collections::BTreeMap;
-use std::error::Error;
 
 use crate::config::{PackageConfig, ScrollEntry};
+use crate::error::Error;
 use crate::ResolvedContext;
-use crate::storage::{load_config, resolve_inner_path};
+use crate::types::AppResult;
+use crate::storage::load_repository_config;
 
 #[derive(Debug, Clone)]
 pub struct ScrollRef {
@@ -13,29 +14,22 @@ pub struct ScrollRef {
     pub dependency_name: Option<String>,
 }
 
-pub fn list_scrolls(context: &ResolvedContext) -> BTreeMap<String, ScrollRef> {
+/// Lists the scrolls of the project and of the installed repositories. Configurations of
+/// repositories which cannot be parsed are reported instead of being skipped.
+pub fn list_scrolls(context: &ResolvedContext) -> AppResult<BTreeMap<String, ScrollRef>> {
     let mut scrolls: BTreeMap<String, ScrollRef> = BTreeMap::new();
 
     for repository in context.current_config.repositories.iter() {
-        let repository_path = format!("ggcode_modules/{}", repository.name);
-        let config_path = format!("{}/ggcode-info.yaml", repository_path);
-        let config = resolve_inner_path(&config_path)
-            .ok()
-            .and_then(|path| load_config(&path).ok());
-
-        match &config {
-            None => {},
-            Some(repository_config) => {
-                for scroll_entry in &repository_config.scrolls {
-                    let full_name = format!("{}/{}", repository.name, scroll_entry.name);
-                    scrolls.insert(full_name.clone(), ScrollRef {
-                        package: repository_config.clone(),
-                        scroll: scroll_entry.clone(),
-                        full_name,
-                        dependency_name: Some(repository.name.clone()),
-                    });
-                }
-            },
+        if let Some(repository_config) = load_repository_config(&repository.name)? {
+            for scroll_entry in &repository_config.scrolls {
+                let full_name = format!("{}/{}", repository.name, scroll_entry.name);
+                scrolls.insert(full_name.clone(), ScrollRef {
+                    package: repository_config.clone(),
+                    scroll: scroll_entry.clone(),
+                    full_name,
+                    dependency_name: Some(repository.name.clone()),
+                });
+            }
         }
     }
 
@@ -49,7 +43,7 @@ pub fn list_scrolls(context: &ResolvedContext) -> BTreeMap<String, ScrollRef> {
         });
     }
 
-    scrolls
+    Ok(scrolls)
 }
 
 pub fn find_scroll_by_name(_context: &ResolvedContext, package: &PackageConfig, name: &String) -> Option<ScrollEntry> {
@@ -59,45 +53,8 @@ pub fn find_scroll_by_name(_context: &ResolvedContext, package: &PackageConfig,
         .map(|e| e.clone())
 }
 
-pub fn find_scroll_by_full_name(context: &ResolvedContext, name: &String) -> Result<ScrollRef, Box<dyn Error>> {
-    let package = &context.current_config;
-
-    for scroll in &package.scrolls {
-        let full_name = format!("@/{}", scroll.name);
-        if *name == full_name {
-            return Ok(ScrollRef {
-                package: package.clone(),
-                scroll: scroll.clone(),
-                full_name: format!("@/{}", scroll.name),
-                dependency_name: None
-            })
-        }
-    }
-
-    for repository in &context.current_config.repositories {
-        let repository_path = format!("ggcode_modules/{}", repository.name);
-        let config_path = format!("{}/ggcode-info.yaml", repository_path);
-        let config = resolve_inner_path(&config_path)
-            .ok()
-            .and_then(|path| load_config(&path).ok());
-
-        match config {
-            None => {},
-            Some(repository_config) => {
-                for scroll in repository_config.scrolls {
-                    let full_name = format!("{}/{}", repository.name, scroll.name);
-                    if *name == full_name {
-                        return Ok(ScrollRef {
-                            package: context.current_config.clone(),
-                            scroll: scroll.clone(),
-                            full_name,
-                            dependency_name: Some(repository.name.clone()),
-                        })
-                    }
-                }
-            },
-        }
-    }
-
-    Err(format!("No scroll with name: {}", name).into())
+pub fn find_scroll_by_full_name(context: &ResolvedContext, name: &String) -> AppResult<ScrollRef> {
+    list_scrolls(context)?
+        .remove(name)
+        .ok_or_else(|| Error::ScrollNotFound { name: name.clone() })
 }
@@ -1,30 +1,32 @@
 use std::{env, fs};
 use std::collections::BTreeMap;
-use std::error::Error;
-use std::io::Write;
+use std::io::{self, Read, Write};
 use std::path::PathBuf;
 
-use console::style;
 use glob::glob;
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
 use relative_path::{RelativePath, RelativePathBuf};
 use serde_yaml::{Mapping, Value};
 
-use crate::config::PackageConfig;
+use crate::capability::Sandbox;
+use crate::config::{DEFAULT_IGNORE_NAME, PackageConfig};
+use crate::error::{Error, ScriptKind};
 use crate::renderer::luau_evaluator::LuauEvaluatorBuilder;
-use crate::renderer::luau_extras::LuauShell;
+use crate::renderer::luau_extras::{LuauFs, LuauLimits, LuauShell};
 use crate::ResolvedContext;
 use crate::types::AppResult;
+use crate::utils::errors::describe_yaml_error;
+use crate::utils::formats::{parse_dotenv, parse_ini, parse_json, parse_toml};
 use crate::utils::merge_yaml;
 
-pub fn resolve_target_path(path: &String) -> Result<PathBuf, Box<dyn Error>> {
+const DEFAULT_IGNORE_PATTERNS: [&str; 5] = [".DS_Store", "Thumbs.db", "*.swp", "*.swo", "*~"];
+
+pub fn resolve_target_path(path: &String) -> AppResult<PathBuf> {
     let path_buf = PathBuf::from(path.to_string());
     let target_path = match path_buf.is_absolute() {
-        true => {
-            let target_path = path_buf
-                .canonicalize()
-                .map_err::<Box<dyn Error>, _>(|e| format!("Invalid path. {}", e).into())?;
-            target_path
-        },
+        true => path_buf
+            .canonicalize()
+            .map_err(|e| Error::InvalidPath { path: path.clone(), reason: e.to_string() })?,
         false => {
             let relative_path = RelativePath::new(path).normalize();
             let target_path = PathBuf::from(relative_path.to_string());
@@ -35,7 +37,7 @@ pub fn resolve_target_path(path: &String) -> Result<PathBuf, Box<dyn Error>> {
     Ok(target_path)
 }
 
-pub fn resolve_package_path(path: &String) -> Result<RelativePathBuf, Box<dyn Error>> {
+pub fn resolve_package_path(path: &String) -> AppResult<RelativePathBuf> {
     let relative_path = RelativePath::new(path).normalize();
     if relative_path.starts_with("@") {
         Ok(RelativePath::new("@").relative(relative_path))
@@ -44,35 +46,44 @@ pub fn resolve_package_path(path: &String) -> Result<RelativePathBuf, Box<dyn Er
     }
 }
 
-pub fn resolve_inner_path(path: &String) -> Result<RelativePathBuf, Box<dyn Error>> {
+pub fn resolve_inner_path(path: &String) -> AppResult<RelativePathBuf> {
     let relative_path = RelativePath::new(path).normalize();
     if relative_path.starts_with("..") {
-        return Err(format!("Invalid path: {}. Could not leave base directory.", style(relative_path).yellow()).into());
+        return Err(Error::InvalidPath {
+            path: relative_path.to_string(),
+            reason: "Could not leave base directory.".to_string(),
+        });
     }
     let normalized_path = relative_path.as_str();
     match normalized_path {
-        "" => return Err("Invalid path. Path should not be empty.".into()),
-        "." => return Err(format!("Invalid path: {}. Path should not point to the project directory.", style(normalized_path).yellow()).into()),
+        "" => Err(Error::InvalidPath {
+            path: String::new(),
+            reason: "Path should not be empty.".to_string(),
+        }),
+        "." => Err(Error::InvalidPath {
+            path: normalized_path.to_string(),
+            reason: "Path should not point to the project directory.".to_string(),
+        }),
         _ => Ok(relative_path)
     }
 }
 
 pub fn resolve_target(context: &ResolvedContext, target_name: Option<String>, target_path: Option<String>) -> AppResult<PathBuf> {
     match (target_path, target_name) {
-        (None, None) => return Err("Invalid usage. Specify `--target` or `--target-path` option.".into()),
+        (None, None) => Err(Error::InvalidUsage {
+            message: "Specify `--target` or `--target-path` option.".to_string(),
+        }),
         (None, Some(name)) => {
             let target = context.current_config.targets
                 .iter()
                 .find(|t| t.name == name)
-                .ok_or::<Box<dyn Error>>(format!("Invalid usage. Unknown target: {}", style(name).yellow()).as_str().into())?;
+                .ok_or_else(|| Error::TargetNotFound { name: name.clone() })?;
             resolve_target_path(&target.path)
         },
         (Some(path), None) => resolve_target_path(&path),
-        (Some(_), Some(_)) => return Err(format!(
-            "Invalid usage. The options {target} and {target_path} should not be used simultaneously.",
-            target = style("target").yellow(),
-            target_path = style("target_path").yellow()
-        ).into()),
+        (Some(_), Some(_)) => Err(Error::InvalidUsage {
+            message: "The options `--target` and `--target-path` should not be used simultaneously.".to_string(),
+        }),
     }
 }
 
@@ -94,7 +105,34 @@ pub fn resolve_search_locations(config: &PackageConfig) -> Vec<RelativePathBuf>
     locations
 }
 
-pub fn save_config(relative_path: &RelativePathBuf, config: PackageConfig) -> Result<(), Box<dyn Error>> {
+/// Lists the shared Tera template directories with the prefix their templates are registered under:
+/// `@/` for the project `lib/templates` directory and `<repository>/` for each dependency.
+pub fn resolve_template_locations(config: &PackageConfig) -> Vec<(String, RelativePathBuf)> {
+    let mut locations: Vec<(String, RelativePathBuf)> = vec![];
+    let path = RelativePathBuf::from("lib");
+    locations.push(("@".to_string(), path));
+    for dependency in &config.repositories {
+        let path = RelativePathBuf::from("ggcode_modules")
+            .join(&dependency.name)
+            .join("lib");
+        locations.push((dependency.name.clone(), path));
+    }
+    locations
+}
+
+pub fn load_library_templates(config: &PackageConfig) -> AppResult<BTreeMap<String, PathBuf>> {
+    let mut map: BTreeMap<String, PathBuf> = BTreeMap::new();
+    for (prefix, library_path) in resolve_template_locations(config) {
+        let templates = load_templates(&library_path.join("templates"), &library_path.join(DEFAULT_IGNORE_NAME))?;
+        // Only Tera files are templates, images and other assets are left to the scrolls
+        for (name, path) in templates.into_iter().filter(|(name, _)| RelativePath::new(name).extension() == Some("tera")) {
+            map.insert(format!("{}/{}", prefix, name), path);
+        }
+    }
+    Ok(map)
+}
+
+pub fn save_config(relative_path: &RelativePathBuf, config: PackageConfig) -> AppResult<()> {
     let current_dir = env::current_dir().unwrap().canonicalize().unwrap();
     let path = relative_path.to_path(current_dir);
 
@@ -110,14 +148,14 @@ pub fn save_config(relative_path: &RelativePathBuf, config: PackageConfig) -> Re
     Ok(())
 }
 
-pub fn rm_scroll(relative_path: &RelativePathBuf) -> Result<(), Box<dyn Error>> {
+pub fn rm_scroll(relative_path: &RelativePathBuf) -> AppResult<()> {
     let current_dir = env::current_dir().unwrap().canonicalize().unwrap();
     let path = relative_path.to_path(current_dir);
     fs::remove_dir_all(path)?;
     Ok(())
 }
 
-pub fn save_string(relative_path: &RelativePathBuf, content: String) -> Result<(), Box<dyn Error>> {
+pub fn save_string(relative_path: &RelativePathBuf, content: String) -> AppResult<()> {
     let current_dir = env::current_dir().unwrap().canonicalize().unwrap();
     let path = relative_path.to_path(current_dir);
 
@@ -138,43 +176,102 @@ pub fn save_string(relative_path: &RelativePathBuf, content: String) -> Result<(
 pub fn load_string(_config: &PackageConfig, relative_path: &RelativePathBuf) -> AppResult<String> {
     let current_dir = env::current_dir().unwrap().canonicalize().unwrap();
     let path = relative_path.to_path(current_dir);
-    let string = fs::read_to_string(&path).or_else(|e| {
-        let error: Box<dyn Error> = format!("Cannot read from: {}. {}", &path.to_str().unwrap(), e).into();
-        return Err(error)
-    })?;
+    let string = fs::read_to_string(&path)
+        .map_err(|cause| Error::File { path: path.clone(), operation: "read from", cause })?;
     Ok(string)
 }
 
-pub fn save_target_file(target_dir: &PathBuf, relative_path: &RelativePathBuf, content: &String, overwrite: bool) -> Result<(), Box<dyn Error>> {
+pub fn load_template_string(path: &PathBuf) -> AppResult<String> {
+    let bytes = fs::read(path)
+        .map_err(|cause| Error::File { path: path.clone(), operation: "read from", cause })?;
+    String::from_utf8(bytes).map_err(|_| Error::InvalidTemplate { path: path.clone() })
+}
+
+pub fn save_target_file(target_dir: &PathBuf, relative_path: &RelativePathBuf, content: &String, overwrite: bool, mode: Option<u32>) -> AppResult<()> {
+    save_target_stream(target_dir, relative_path, &mut content.as_bytes(), overwrite, mode)
+}
+
+pub fn save_target_stream<R: Read + ?Sized>(target_dir: &PathBuf, relative_path: &RelativePathBuf, reader: &mut R, overwrite: bool, mode: Option<u32>) -> AppResult<()> {
     let path = relative_path.to_path(target_dir);
 
-    let prefix = path.parent().unwrap();
-    fs::create_dir_all(prefix).unwrap();
+    if let Some(prefix) = path.parent() {
+        fs::create_dir_all(prefix)
+            .map_err(|cause| Error::File { path: prefix.to_path_buf(), operation: "create directory", cause })?;
+    }
 
     if overwrite || !path.exists() {
         let mut f = fs::OpenOptions::new()
             .write(true)
             .truncate(true)
             .create(true)
-            .open(path)
-            .expect("Couldn't open target file");
+            .open(&path)
+            .map_err(|cause| Error::File { path: path.clone(), operation: "write to", cause })?;
+
+        io::copy(reader, &mut f)
+            .map_err(|cause| Error::File { path: path.clone(), operation: "write to", cause })?;
 
-        f.write_all(content.as_bytes()).unwrap();
+        if let Some(mode) = mode {
+            save_file_mode(&path, mode)?;
+        }
     }
 
     Ok(())
 }
 
-pub fn load_config(relative_path: &RelativePathBuf) -> Result<PackageConfig, Box<dyn Error>> {
+#[cfg(unix)]
+pub fn load_file_mode(path: &PathBuf) -> Option<u32> {
+    use std::os::unix::fs::PermissionsExt;
+    fs::metadata(path)
+        .ok()
+        .map(|metadata| metadata.permissions().mode() & 0o7777)
+}
+
+#[cfg(not(unix))]
+pub fn load_file_mode(_path: &PathBuf) -> Option<u32> {
+    None
+}
+
+#[cfg(unix)]
+pub fn save_file_mode(path: &PathBuf, mode: u32) -> AppResult<()> {
+    use std::os::unix::fs::PermissionsExt;
+    fs::set_permissions(path, fs::Permissions::from_mode(mode))
+        .map_err(|cause| Error::File { path: path.clone(), operation: "change mode of", cause })
+}
+
+#[cfg(not(unix))]
+pub fn save_file_mode(_path: &PathBuf, _mode: u32) -> AppResult<()> {
+    Ok(())
+}
+
+pub fn parse_file_mode(mode: &str) -> AppResult<u32> {
+    let digits = mode.trim_start_matches("0o");
+    match u32::from_str_radix(digits, 8) {
+        Ok(value) if value <= 0o7777 => Ok(value),
+        _ => Err(Error::InvalidFileMode { mode: mode.to_string() }),
+    }
+}
+
+pub fn load_config(relative_path: &RelativePathBuf) -> AppResult<PackageConfig> {
     let current_dir = env::current_dir().unwrap().canonicalize().unwrap();
     let path = relative_path.to_path(current_dir);
 
-    let f = fs::File::open(path)?;
-    let config = serde_yaml::from_reader(f)?;
-    Ok(config)
+    let source = fs::read_to_string(&path)
+        .map_err(|cause| Error::File { path: path.clone(), operation: "read from", cause })?;
+    serde_yaml::from_str(&source).map_err(|e| describe_yaml_error(relative_path.as_str(), &source, &e))
 }
 
-// pub fn load_scroll(relative_path: &RelativePathBuf) -> Result<ScrollConfig, Box<dyn Error>> {
+/// Loads the configuration of an installed repository, repositories which are not installed have none.
+pub fn load_repository_config(name: &String) -> AppResult<Option<PackageConfig>> {
+    let current_dir = env::current_dir().unwrap().canonicalize().unwrap();
+    let relative_path = resolve_inner_path(&format!("ggcode_modules/{}/ggcode-info.yaml", name))?;
+
+    match relative_path.to_path(current_dir).is_file() {
+        true => Ok(Some(load_config(&relative_path)?)),
+        false => Ok(None),
+    }
+}
+
+// pub fn load_scroll(relative_path: &RelativePathBuf) -> AppResult<ScrollConfig> {
 //     let current_dir = env::current_dir().unwrap().canonicalize().unwrap();
 //     let path = relative_path.to_path(current_dir);
 //
@@ -183,23 +280,39 @@ pub fn load_config(relative_path: &RelativePathBuf) -> Result<PackageConfig, Box
 //     Ok(config)
 // }
 
-pub fn load_yaml(relative_path: &RelativePathBuf) -> Result<Value, Box<dyn Error>> {
+pub fn load_yaml(relative_path: &RelativePathBuf) -> AppResult<Value> {
     let current_dir = env::current_dir().unwrap().canonicalize().unwrap();
     let path = relative_path.to_path(current_dir);
 
-    let f = fs::File::open(path)?;
-    let config = serde_yaml::from_reader(f)?;
-    Ok(config)
+    let source = fs::read_to_string(&path)
+        .map_err(|cause| Error::File { path: path.clone(), operation: "read from", cause })?;
+    serde_yaml::from_str(&source).map_err(|e| describe_yaml_error(relative_path.as_str(), &source, &e))
+}
+
+pub fn load_formatted<F: Fn(&str) -> AppResult<Value>>(relative_path: &RelativePathBuf, parse: F) -> AppResult<Value> {
+    let current_dir = env::current_dir().unwrap().canonicalize().unwrap();
+    let path = relative_path.to_path(current_dir);
+
+    let source = fs::read_to_string(&path)
+        .map_err(|cause| Error::File { path: path.clone(), operation: "read from", cause })?;
+    parse(&source).map_err(|e| match e {
+        Error::Parse { message, location, suggestion, .. } => Error::Parse { path: relative_path.to_string(), message, location, suggestion },
+        e => e,
+    })
 }
 
-pub fn load_luau(relative_path: &RelativePathBuf, search_locations: &Vec<RelativePathBuf>) -> Result<Value, Box<dyn Error>> {
+pub fn load_luau(relative_path: &RelativePathBuf, search_locations: &Vec<RelativePathBuf>, fs: &LuauFs, sandbox: &Option<Sandbox>, limits: LuauLimits) -> AppResult<Value> {
     let current_dir = env::current_dir().unwrap().canonicalize().unwrap();
     let path = relative_path.to_path(&current_dir);
 
     let source = fs::read_to_string(&path)?;
 
     let mut builder = LuauEvaluatorBuilder::new()
-        .enable_shell(LuauShell);
+        .enable_shell(LuauShell::default())
+        .enable_fs(fs.clone())
+        .with_sandbox(sandbox.clone())
+        .with_limits(limits)
+        .with_kind(ScriptKind::Variables);
 
     for rp in search_locations {
         builder = builder.with_path_entry(&rp.to_path(&current_dir));
@@ -209,14 +322,14 @@ pub fn load_luau(relative_path: &RelativePathBuf, search_locations: &Vec<Relativ
         .with_global("SOURCE_PATH", &path.to_str().unwrap().to_string())
         .build()?;
 
-    let config = evaluator.eval_value(&source)?;
+    let config = evaluator.eval_file_value(relative_path.as_str(), &source)?;
 
     // println!("Config: {}", serde_yaml::to_string(&config)?);
 
     Ok(config)
 }
 
-pub fn load_variables(values_directory_path: &RelativePathBuf, search_locations: &Vec<RelativePathBuf>) -> Result<Value, Box<dyn Error>> {
+pub fn load_variables(values_directory_path: &RelativePathBuf, search_locations: &Vec<RelativePathBuf>, fs: &LuauFs, sandbox: &Option<Sandbox>, limits: LuauLimits) -> AppResult<Value> {
     let pattern = format!("{}/**/*", values_directory_path);
 
     let mut merged_value: Value = Value::Mapping(Mapping::new());
@@ -228,14 +341,23 @@ pub fn load_variables(values_directory_path: &RelativePathBuf, search_locations:
                     let relative_entry_path = RelativePathBuf::from_path(entry_path).unwrap();
                     let relative_variables_path = values_directory_path.relative(&relative_entry_path);
 
-                    let config = match relative_entry_path.extension() {
+                    // Dotenv files are commonly named just `.env`, so they are exposed as `env`
+                    let (extension, file_stem) = match relative_variables_path.file_name() {
+                        Some(".env") => (Some("env"), "env"),
+                        _ => (relative_variables_path.extension(), relative_variables_path.file_stem().unwrap()),
+                    };
+
+                    let config = match extension {
                         Some("yaml") => Some(load_yaml(&relative_entry_path)?),
-                        Some("luau") => Some(load_luau(&relative_entry_path, search_locations)?),
+                        Some("luau") => Some(load_luau(&relative_entry_path, search_locations, fs, sandbox, limits)?),
+                        Some("json") => Some(load_formatted(&relative_entry_path, parse_json)?),
+                        Some("toml") => Some(load_formatted(&relative_entry_path, parse_toml)?),
+                        Some("env") => Some(load_formatted(&relative_entry_path, parse_dotenv)?),
+                        Some("ini") => Some(load_formatted(&relative_entry_path, parse_ini)?),
                         _ => None
                     };
 
                     if let Some(value) = &config {
-                        let file_stem = relative_variables_path.file_stem().unwrap();
                         let parent = relative_variables_path.parent().unwrap();
 
                         let mut proto = Value::Mapping(Mapping::new());
@@ -258,9 +380,49 @@ pub fn load_variables(values_directory_path: &RelativePathBuf, search_locations:
     Ok(merged_value)
 }
 
-pub fn load_templates(templates_directory_path: &RelativePathBuf) -> BTreeMap<String, PathBuf> {
+pub fn load_templates(templates_directory_path: &RelativePathBuf, ignore_file_path: &RelativePathBuf) -> AppResult<BTreeMap<String, PathBuf>> {
+    let current_dir = env::current_dir().unwrap().canonicalize().unwrap();
+    let ignore = load_ignore(
+        &templates_directory_path.to_path(&current_dir),
+        &ignore_file_path.to_path(&current_dir))?;
+
     let pattern = format!("{}/**/*", templates_directory_path);
-    return load_glob(&pattern, templates_directory_path);
+    let templates = load_glob(&pattern, templates_directory_path)
+        .into_iter()
+        .filter(|(_, path)| !ignore.matched_path_or_any_parents(path, false).is_ignore())
+        .collect();
+
+    Ok(templates)
+}
+
+/// Builds a matcher for the files that should never be picked up from the templates directory.
+/// Patterns from the ignore file use gitignore syntax and are resolved against the templates
+/// directory; editor leftovers and OS metadata files are always ignored.
+pub fn load_ignore(templates_directory_path: &PathBuf, ignore_file_path: &PathBuf) -> AppResult<Gitignore> {
+    let mut builder = GitignoreBuilder::new(templates_directory_path);
+
+    for pattern in DEFAULT_IGNORE_PATTERNS {
+        builder.add_line(None, pattern)
+            .map_err(|e| Error::InvalidPattern { pattern: pattern.to_string(), message: e.to_string() })?;
+    }
+
+    if ignore_file_path.is_file() {
+        if let Some(e) = builder.add(ignore_file_path) {
+            return Err(Error::Parse {
+                path: ignore_file_path.display().to_string(),
+                message: e.to_string(),
+                location: None,
+                suggestion: None,
+            });
+        }
+    }
+
+    builder.build().map_err(|e| Error::Parse {
+        path: ignore_file_path.display().to_string(),
+        message: e.to_string(),
+        location: None,
+        suggestion: None,
+    })
 }
 
 pub fn load_glob(pattern: &String, scripts_directory_path: &RelativePathBuf) -> BTreeMap<String, PathBuf> {
@@ -286,9 +448,11 @@ mod tests {
 
     use indoc::indoc;
     use mlua::{Lua, LuaSerdeExt};
+    use relative_path::RelativePathBuf;
     use serde_yaml::Value;
 
-    use crate::storage::resolve_target_path;
+    use crate::storage::{load_formatted, load_repository_config, parse_file_mode, resolve_target_path};
+    use crate::utils::formats::parse_toml;
 
     #[test]
     fn resolve_target_path_test() -> Result<(), Box<dyn Error>> {
@@ -313,6 +477,98 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn parse_file_mode_test() -> Result<(), Box<dyn Error>> {
+        assert_eq!(parse_file_mode("755")?, 0o755);
+        assert_eq!(parse_file_mode("0644")?, 0o644);
+        assert_eq!(parse_file_mode("0o700")?, 0o700);
+        assert!(parse_file_mode("rwx").is_err());
+        assert!(parse_file_mode("17777").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn load_formatted_missing_test() {
+        let error = load_formatted(&RelativePathBuf::from("variables/missing.toml"), parse_toml).unwrap_err();
+        match error {
+            crate::error::Error::File { path, operation, .. } => {
+                assert!(path.ends_with("variables/missing.toml"));
+                assert_eq!(operation, "read from");
+            }
+            e => panic!("Unexpected error: {}", e),
+        }
+    }
+
+    #[test]
+    fn load_repository_config_test() -> Result<(), Box<dyn Error>> {
+        // Repositories which are not installed are skipped, invalid names are not
+        assert!(load_repository_config(&"not-installed".to_string())?.is_none());
+        assert!(load_repository_config(&"../../outside".to_string()).is_err());
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn save_target_file_mode_test() -> Result<(), Box<dyn Error>> {
+        use std::os::unix::fs::PermissionsExt;
+        use crate::storage::{load_file_mode, save_target_file};
+
+        let target_dir = std::env::temp_dir().join(format!("ggcode-mode-{}", std::process::id()));
+        let relative_path = RelativePathBuf::from("scripts/deploy.sh");
+        save_target_file(&target_dir, &relative_path, &"#!/bin/sh\n".to_string(), true, Some(0o755))?;
+
+        let path = relative_path.to_path(&target_dir);
+        assert_eq!(std::fs::metadata(&path)?.permissions().mode() & 0o777, 0o755);
+        assert_eq!(load_file_mode(&path), Some(0o755));
+
+        std::fs::remove_dir_all(&target_dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn save_target_file_error_test() -> Result<(), Box<dyn Error>> {
+        use crate::storage::save_target_file;
+
+        let target_dir = std::env::temp_dir().join(format!("ggcode-save-{}", std::process::id()));
+        std::fs::create_dir_all(target_dir.join("directory"))?;
+        std::fs::write(target_dir.join("file.txt"), "")?;
+
+        let content = "content".to_string();
+        for (relative_path, expected) in [("file.txt/nested.txt", "create directory"), ("directory", "write to")] {
+            match save_target_file(&target_dir, &RelativePathBuf::from(relative_path), &content, true, None) {
+                Err(crate::error::Error::File { operation, .. }) => assert_eq!(operation, expected),
+                result => panic!("Unexpected result for {}: {:?}", relative_path, result),
+            }
+        }
+
+        std::fs::remove_dir_all(&target_dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn load_ignore_test() -> Result<(), Box<dyn Error>> {
+        use crate::storage::load_ignore;
+
+        let scroll_dir = std::env::temp_dir().join(format!("ggcode-ignore-{}", std::process::id()));
+        let templates_dir = scroll_dir.join("templates");
+        let ignore_file = scroll_dir.join(".ggcodeignore");
+        std::fs::create_dir_all(&templates_dir)?;
+        std::fs::write(&ignore_file, "# drafts\ndrafts/\n*.bak\n!keep.bak\n")?;
+
+        let ignore = load_ignore(&templates_dir, &ignore_file)?;
+        let is_ignored = |path: &str| ignore.matched_path_or_any_parents(templates_dir.join(path), false).is_ignore();
+
+        assert!(is_ignored(".DS_Store"));
+        assert!(is_ignored("src/.main.rs.swp"));
+        assert!(is_ignored("drafts/README.md.tera"));
+        assert!(is_ignored("docs/old.bak"));
+        assert!(!is_ignored("keep.bak"));
+        assert!(!is_ignored("src/main.rs.tera"));
+
+        std::fs::remove_dir_all(&scroll_dir)?;
+        Ok(())
+    }
+
     #[test]
     fn load_lua_config_test() -> Result<(), Box<dyn Error>> {
         let lua = Lua::new();
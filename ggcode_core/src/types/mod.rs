@@ -1,4 +1 @@
-use std::error::Error;
-
-pub type ErrorBox = Box<dyn Error>;
-pub type AppResult<T> = Result<T, ErrorBox>;
+pub type AppResult<T> = Result<T, crate::error::Error>;
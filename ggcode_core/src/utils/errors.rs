@@ -1,11 +1,12 @@
 use std::cmp;
-use std::error::Error;
-use console::style;
+use std::error::Error as StdError;
 
 use lazy_static::lazy_static;
 use regex::Regex;
 
-use crate::types::{AppResult, ErrorBox};
+use crate::error::{Error, SourceLocation};
+use crate::types::AppResult;
+use crate::utils::strings::suggest;
 
 pub enum ErrorDescription {
     SourceError(SourceErrorData)
@@ -16,154 +17,288 @@ pub enum ErrorDescription {
 pub struct SourceErrorData {
     pub location: String,
     pub line: usize,
+    pub column: Option<usize>,
     pub details: String,
     pub is_pointed: bool,
+    /// Luau stack traceback, when the error comes from a script
+    pub traceback: Option<String>,
 }
 
-pub fn describe_error(e: &ErrorBox) -> AppResult<Option<ErrorDescription>> {
-    if e.is::<mlua::Error>() {
-        return describe_mlua_error(e);
+pub fn describe_error(e: &Error) -> AppResult<Option<ErrorDescription>> {
+    match e {
+        Error::Script { cause, .. } => describe_mlua_error(cause),
+        Error::Template { name: path, message, location: Some(location), .. } |
+        Error::Parse { path, message, location: Some(location), .. } => {
+            Ok(Some(ErrorDescription::SourceError(SourceErrorData {
+                location: path.clone(),
+                line: location.line,
+                column: Some(location.column),
+                details: message.clone(),
+                is_pointed: false,
+                traceback: None,
+            })))
+        }
+        _ => Ok(None),
     }
-    Ok(None)
 }
 
-fn describe_mlua_error(e: &Box<dyn Error>) -> AppResult<Option<ErrorDescription>> {
-    let option: Option<(String, &Box<dyn Error>)>= match e.downcast_ref::<mlua::Error>().unwrap() {
-        mlua::Error::SyntaxError { message, .. } => Some((message.clone(), e)),
-        mlua::Error::RuntimeError(message) => Some((message.clone(), e)),
-        mlua::Error::CallbackError { traceback, cause } => Some((cause.to_string(), e)),
-        mlua::Error::MemoryError(_me) => {
-            eprintln!("{} {} handler is not implemented yet", style("[DEBUG]").cyan(), "MemoryError");
-            None
-        }
-        // mlua::Error::GarbageCollectorError(_) => {
-        //     eprintln!("{} {} handler is not implemented yet", style("[DEBUG]").cyan(), "GarbageCollectorError");
-        //     None
-        // }
-        mlua::Error::SafetyError(_) => {
-            eprintln!("{} {} handler is not implemented yet", style("[DEBUG]").cyan(), "SafetyError");
-            None
-        }
-        mlua::Error::MemoryLimitNotAvailable => {
-            eprintln!("{} {} handler is not implemented yet", style("[DEBUG]").cyan(), "MemoryLimitNotAvailable");
-            None
-        }
-        mlua::Error::RecursiveMutCallback => {
-            eprintln!("{} {} handler is not implemented yet", style("[DEBUG]").cyan(), "RecursiveMutCallback");
-            None
-        }
-        mlua::Error::CallbackDestructed => {
-            eprintln!("{} {} handler is not implemented yet", style("[DEBUG]").cyan(), "CallbackDestructed");
-            None
-        }
-        mlua::Error::StackError => {
-            eprintln!("{} {} handler is not implemented yet", style("[DEBUG]").cyan(), "StackError");
-            None
-        }
-        mlua::Error::BindError => {
-            eprintln!("{} {} handler is not implemented yet", style("[DEBUG]").cyan(), "BindError");
-            None
-        }
-        mlua::Error::BadArgument { .. } => {
-            eprintln!("{} {} handler is not implemented yet", style("[DEBUG]").cyan(), "BadArgument");
-            None
-        }
-        mlua::Error::ToLuaConversionError { .. } => {
-            eprintln!("{} {} handler is not implemented yet", style("[DEBUG]").cyan(), "ToLuaConversionError");
-            None
-        }
-        mlua::Error::FromLuaConversionError { .. } => {
-            eprintln!("{} {} handler is not implemented yet", style("[DEBUG]").cyan(), "FromLuaConversionError");
-            None
-        }
-        mlua::Error::CoroutineInactive => {
-            eprintln!("{} {} handler is not implemented yet", style("[DEBUG]").cyan(), "CoroutineInactive");
-            None
-        }
-        mlua::Error::UserDataTypeMismatch => {
-            eprintln!("{} {} handler is not implemented yet", style("[DEBUG]").cyan(), "UserDataTypeMismatch");
-            None
-        }
-        mlua::Error::UserDataDestructed => {
-            eprintln!("{} {} handler is not implemented yet", style("[DEBUG]").cyan(), "UserDataDestructed");
-            None
-        }
-        mlua::Error::UserDataBorrowError => {
-            eprintln!("{} {} handler is not implemented yet", style("[DEBUG]").cyan(), "UserDataBorrowError");
-            None
-        }
-        mlua::Error::UserDataBorrowMutError => {
-            eprintln!("{} {} handler is not implemented yet", style("[DEBUG]").cyan(), "UserDataBorrowMutError");
-            None
-        }
-        mlua::Error::MetaMethodRestricted(_) => {
-            eprintln!("{} {} handler is not implemented yet", style("[DEBUG]").cyan(), "MetaMethodRestricted");
-            None
-        }
-        mlua::Error::MetaMethodTypeError { .. } => {
-            eprintln!("{} {} handler is not implemented yet", style("[DEBUG]").cyan(), "MetaMethodTypeError");
-            None
-        }
-        mlua::Error::MismatchedRegistryKey => {
-            eprintln!("{} {} handler is not implemented yet", style("[DEBUG]").cyan(), "MismatchedRegistryKey");
-            None
-        }
-        mlua::Error::CallbackError { .. } => {
-            eprintln!("{} {} handler is not implemented yet", style("[DEBUG]").cyan(), "CallbackError");
-            None
-        }
-        mlua::Error::PreviouslyResumedPanic => {
-            eprintln!("{} {} handler is not implemented yet", style("[DEBUG]").cyan(), "PreviouslyResumedPanic");
-            None
-        }
-        mlua::Error::SerializeError(_) => {
-            eprintln!("{} {} handler is not implemented yet", style("[DEBUG]").cyan(), "SerializeError");
-            None
-        }
-        mlua::Error::DeserializeError(_) => {
-            eprintln!("{} {} handler is not implemented yet", style("[DEBUG]").cyan(), "DeserializeError");
-            None
-        }
-        mlua::Error::ExternalError(_) => {
-            eprintln!("{} {} handler is not implemented yet", style("[DEBUG]").cyan(), "ExternalError");
-            None
-        }
-        mlua::Error::WithContext { .. } => {
-            eprintln!("{} {} handler is not implemented yet", style("[DEBUG]").cyan(), "WithContext");
-            None
-        },
-        _ => {
-            eprintln!("{} {} handler is not implemented yet", style("[DEBUG]").cyan(), "UnknownError");
-            None
+/// Converts a YAML failure into a parse error of the file, keeping the position reported by the
+/// parser and suggesting the closest known key for unknown fields.
+pub fn describe_yaml_error(path: &str, source_code: &str, e: &serde_yaml::Error) -> Error {
+    lazy_static! {
+        static ref RE_LOCATION: Regex = Regex::new(r#" at line \d+ column \d+$"#).unwrap();
+        static ref RE_UNKNOWN_FIELD: Regex = Regex::new(r#"unknown (field|variant) `(?P<name>[^`]*)`, expected (one of )?(?P<expected>.*)$"#).unwrap();
+        static ref RE_NAME: Regex = Regex::new(r#"`(?P<name>[^`]*)`"#).unwrap();
+    }
+
+    let message = RE_LOCATION.replace(&e.to_string(), "").to_string();
+    let suggestion = RE_UNKNOWN_FIELD.captures(&message).and_then(|unknown| {
+        let expected: Vec<&str> = RE_NAME.captures_iter(unknown.name("expected").unwrap().as_str())
+            .map(|name| name.name("name").unwrap().as_str())
+            .collect();
+        suggest(&unknown["name"], expected)
+    });
+
+    Error::Parse {
+        path: path.to_string(),
+        message,
+        location: locate(source_code, e.location().map(|location| (location.line().saturating_sub(1), location.column().saturating_sub(1)))),
+        suggestion,
+    }
+}
+
+/// Converts a Tera failure into a template error, locating the failing expression in the source
+/// and suggesting the closest context key for unknown variables.
+pub fn describe_tera_error(name: &str, source_code: &str, e: &tera::Error, context: Option<&tera::Context>) -> Error {
+    lazy_static! {
+        static ref RE_PARSE_POSITION: Regex = Regex::new(r#"(?m)^\s*--> (?P<line>\d+):(?P<column>\d+)$"#).unwrap();
+        static ref RE_PARSE_DETAILS: Regex = Regex::new(r#"(?m)^\s*= (?P<details>.*)$"#).unwrap();
+        static ref RE_VARIABLE: Regex = Regex::new(r#"Variable `(?P<name>[^`]+)` not found"#).unwrap();
+        static ref RE_FILTER: Regex = Regex::new(r#"Filter (call )?'(?P<name>[^']+)'"#).unwrap();
+        static ref RE_FUNCTION: Regex = Regex::new(r#"Function (call )?'(?P<name>[^']+)'"#).unwrap();
+        static ref RE_TEST: Regex = Regex::new(r#"Test (call )?'(?P<name>[^']+)'"#).unwrap();
+    }
+
+    let mut messages = vec![e.to_string()];
+    let mut cause = e.source();
+    while let Some(c) = cause {
+        messages.push(c.to_string());
+        cause = c.source();
+    }
+    let chain = messages.join("\n");
+
+    // Syntax errors come from the parser with an exact position and an excerpt of their own
+    if let Some(position) = RE_PARSE_POSITION.captures(&chain) {
+        let line = position["line"].parse::<usize>().unwrap_or(1);
+        let column = position["column"].parse::<usize>().unwrap_or(1);
+        let details = RE_PARSE_DETAILS.captures(&chain)
+            .map(|c| c["details"].to_string())
+            .unwrap_or_default();
+        return Error::Template {
+            name: name.to_string(),
+            message: format!("{}. {}", messages[0], details),
+            location: locate(source_code, Some((line.saturating_sub(1), column.saturating_sub(1)))),
+            suggestion: None,
+        };
+    }
+
+    let message = messages.join(". ");
+    let mut position = None;
+    let mut suggestion = None;
+    if let Some(variable) = RE_VARIABLE.captures(&message) {
+        let variable = &variable["name"];
+        position = locate_in_tags(source_code, &format!(r"\b{}\b", regex::escape(variable)));
+        suggestion = context.and_then(|context| suggest_variable(variable, context));
+    } else if let Some(filter) = RE_FILTER.captures(&message) {
+        position = locate_in_tags(source_code, &format!(r"\|\s*{}\b", regex::escape(&filter["name"])));
+    } else if let Some(function) = RE_FUNCTION.captures(&message) {
+        position = locate_in_tags(source_code, &format!(r"\b{}\s*\(", regex::escape(&function["name"])));
+    } else if let Some(test) = RE_TEST.captures(&message) {
+        position = locate_in_tags(source_code, &format!(r"\bis\s+(not\s+)?{}\b", regex::escape(&test["name"])));
+    }
+
+    Error::Template {
+        name: name.to_string(),
+        message,
+        location: locate(source_code, position),
+        suggestion,
+    }
+}
+
+fn locate(source_code: &str, position: Option<(usize, usize)>) -> Option<Box<SourceLocation>> {
+    position.map(|(line, column)| Box::new(SourceLocation {
+        source_code: source_code.to_string(),
+        line,
+        column,
+    }))
+}
+
+/// Finds the first match of the pattern inside of a `{{ }}` or `{% %}` tag, as zero-based line and column.
+fn locate_in_tags(source_code: &str, pattern: &str) -> Option<(usize, usize)> {
+    let re = Regex::new(pattern).ok()?;
+    let position = re.find_iter(source_code)
+        .map(|m| m.start())
+        .find(|&start| {
+            let before = &source_code[..start];
+            let opened = cmp::max(before.rfind("{{").map_or(-1, |i| i as i64), before.rfind("{%").map_or(-1, |i| i as i64));
+            let closed = cmp::max(before.rfind("}}").map_or(-1, |i| i as i64), before.rfind("%}").map_or(-1, |i| i as i64));
+            opened > closed
+        })
+        .map(|start| {
+            let before = &source_code[..start];
+            let line = before.matches('\n').count();
+            let column = before.chars().rev().take_while(|c| *c != '\n').count();
+            (line, column)
+        });
+    position
+}
+
+/// Walks the dotted path of an unknown variable and suggests a replacement for the first missing segment.
+fn suggest_variable(variable: &str, context: &tera::Context) -> Option<String> {
+    let mut current = context.clone().into_json();
+    let segments: Vec<&str> = variable.split('.').collect();
+    for (i, segment) in segments.iter().enumerate() {
+        let object = current.as_object()?;
+        match object.get(*segment) {
+            Some(value) => current = value.clone(),
+            None => {
+                let replacement = suggest(segment, object.keys().map(|key| key.as_str()))?;
+                let mut path: Vec<&str> = segments[..i].to_vec();
+                path.push(&replacement);
+                return Some(path.join("."));
+            }
         }
+    }
+    None
+}
+
+fn describe_mlua_error(e: &mlua::Error) -> AppResult<Option<ErrorDescription>> {
+    lazy_static! {
+        static ref RE_ERROR_V1: Regex = Regex::new(r#"(?m)^((?P<type>[^:]*): )?\[string "(?P<location>[^"]*)"]:(?P<line>[\d]+): (?P<details>.*)$"#).unwrap();
+        static ref RE_ERROR_V2: Regex = Regex::new(r#"(?m)^((?P<type>[^:]*): )?(?P<location>[^:]*):(?P<line>[\d]+): (?P<details>.*)$"#).unwrap();
+        static ref RE_ERROR_STACK_OVERFLOW: Regex = Regex::new(r#"(?m)^C stack overflow$"#).unwrap();
+        static ref RE_FRAME_V1: Regex = Regex::new(r#"(?m)^\s*\[string "(?P<location>[^"]*)"]:(?P<line>[\d]+): "#).unwrap();
+        static ref RE_FRAME_V2: Regex = Regex::new(r#"(?m)^\s*(?P<location>[^\[\s][^:]*):(?P<line>[\d]+): "#).unwrap();
+    }
+
+    let message = describe_mlua_message(e);
+    let traceback = innermost_traceback(e);
+
+    if RE_ERROR_STACK_OVERFLOW.is_match(&message) {
+        return Ok(Some(ErrorDescription::SourceError(SourceErrorData { location: "".into(), line: 0, column: None, details: message, is_pointed: false, traceback })))
+    }
+
+    let zero_based = |line: &str| -> AppResult<usize> {
+        let line = line.parse::<usize>()
+            .map_err(|e| Error::Parse { path: String::new(), message: format!("Invalid line number: {}. {}", line, e), location: None, suggestion: None })?;
+        Ok(line.saturating_sub(1))
     };
 
-    if let Some((message, _)) = option {
-        lazy_static! {
-            static ref RE_ERROR_V1: Regex = Regex::new(r#"(?m)^((?P<type>[^:]*): )?\[string "(?P<location>[^"]*)"]:(?P<line>[\d]+): (?P<details>.*)$"#).unwrap();
-            static ref RE_ERROR_V2: Regex = Regex::new(r#"(?m)^((?P<type>[^:]*): )?(?P<location>[^:]*):(?P<line>[\d]+): (?P<details>.*)$"#).unwrap();
-            static ref RE_ERROR_STACK_OVERFLOW: Regex = Regex::new(r#"(?m)^C stack overflow$"#).unwrap();
-        }
+    if let Some(v) = &RE_ERROR_V1.captures(&message) {
+        let location = v.name("location").unwrap().as_str().to_string();
+        let details = v.name("details").unwrap().as_str().to_string();
+        let line = zero_based(v.name("line").unwrap().as_str())?;
+        return Ok(Some(ErrorDescription::SourceError(SourceErrorData { location, line, column: None, details, is_pointed: false, traceback })))
+    }
 
-        if let Some(_) = &RE_ERROR_STACK_OVERFLOW.captures(&message) {
-            return Ok(Some(ErrorDescription::SourceError(SourceErrorData { location: "".into(), line: 0, details: message, is_pointed: false })))
-        }
+    if let Some(v) = &RE_ERROR_V2.captures(&message) {
+        let location = v.name("location").unwrap().as_str().to_string();
+        let details = v.name("details").unwrap().as_str().to_string();
+        let line = zero_based(v.name("line").unwrap().as_str())?;
+        return Ok(Some(ErrorDescription::SourceError(SourceErrorData { location, line, column: None, details, is_pointed: true, traceback })))
+    }
 
-        if let Some(v) = &RE_ERROR_V1.captures(&message) {
+    // Errors raised by Rust callbacks carry no location, the innermost Luau frame points at the call
+    if let Some(traceback_string) = &traceback {
+        let frame = RE_FRAME_V1.captures(traceback_string)
+            .map(|v| (v, false))
+            .or_else(|| RE_FRAME_V2.captures(traceback_string).map(|v| (v, true)));
+        if let Some((v, is_pointed)) = frame {
             let location = v.name("location").unwrap().as_str().to_string();
-            let details = v.name("details").unwrap().as_str().to_string();
-            let line = v.name("line").unwrap().as_str().parse::<usize>()?;
-            let line = cmp::max(0, (line as i32) - 1) as usize;
-            return Ok(Some(ErrorDescription::SourceError(SourceErrorData { location, line, details, is_pointed: false })))
+            let line = zero_based(v.name("line").unwrap().as_str())?;
+            return Ok(Some(ErrorDescription::SourceError(SourceErrorData { location, line, column: None, details: message, is_pointed, traceback })))
         }
+    }
 
-        if let Some(v) = &RE_ERROR_V2.captures(&message) {
-            let location = v.name("location").unwrap().as_str().to_string();
-            let details = v.name("details").unwrap().as_str().to_string();
-            let line = v.name("line").unwrap().as_str().parse::<usize>()?;
-            let line = cmp::max(0, (line as i32) - 1) as usize;
-            return Ok(Some(ErrorDescription::SourceError(SourceErrorData { location, line, details, is_pointed: true })))
+    Ok(None)
+}
+
+/// Describes every kind of Luau error in user-facing terms, unwrapping the causes of callbacks.
+pub fn describe_mlua_message(e: &mlua::Error) -> String {
+    match e {
+        mlua::Error::SyntaxError { message, .. } => message.clone(),
+        mlua::Error::RuntimeError(message) => split_traceback(message).0,
+        mlua::Error::MemoryError(message) => format!("Not enough memory. {}", message),
+        mlua::Error::SafetyError(message) => format!("Unsafe operation. {}", message),
+        mlua::Error::MemoryLimitNotAvailable => "Memory limits are not available for scripts.".to_string(),
+        mlua::Error::RecursiveMutCallback => "A callback was called recursively while it was still running.".to_string(),
+        mlua::Error::CallbackDestructed => "A callback was called after it had been destroyed.".to_string(),
+        mlua::Error::StackError => "Script stack overflow. Too many values were passed between Luau and a callback.".to_string(),
+        mlua::Error::BindError => "Too many arguments were bound to a function.".to_string(),
+        mlua::Error::BadArgument { to, pos, name, cause } => {
+            // Methods are named `Type.method` and receive themselves as the first argument
+            let pos = match to.as_deref().is_some_and(|to| to.contains('.')) {
+                true => pos.saturating_sub(1),
+                false => *pos,
+            };
+            let argument = match name {
+                Some(name) => format!("#{} ({})", pos, name),
+                None => format!("#{}", pos),
+            };
+            format!("Bad argument {} to {}. {}", argument, to.as_deref().unwrap_or("function"), describe_mlua_message(cause))
         }
+        mlua::Error::ToLuaConversionError { from, to, message } => with_details(
+            format!("Cannot convert {} to Luau {}.", from, to), message),
+        mlua::Error::FromLuaConversionError { from, to, message } => with_details(
+            format!("Cannot convert Luau {} to {}.", from, to), message),
+        mlua::Error::CoroutineInactive => "Cannot resume a coroutine which is finished or running.".to_string(),
+        mlua::Error::UserDataTypeMismatch => "A module method was called on a value of another type, use `:` to call methods.".to_string(),
+        mlua::Error::UserDataDestructed => "A module was used after it had been destroyed.".to_string(),
+        mlua::Error::UserDataBorrowError => "A module is already in use by a running method.".to_string(),
+        mlua::Error::UserDataBorrowMutError => "A module is already in use by a running method.".to_string(),
+        mlua::Error::MetaMethodRestricted(method) => format!("Metamethod {} cannot be overridden.", method),
+        mlua::Error::MetaMethodTypeError { method, type_name, message } => with_details(
+            format!("Metamethod {} cannot be a {}.", method, type_name), message),
+        mlua::Error::MismatchedRegistryKey => "A value was used with another Luau state than the one it was created in.".to_string(),
+        mlua::Error::CallbackError { cause, .. } => describe_mlua_message(cause),
+        mlua::Error::PreviouslyResumedPanic => "A previous script failed with a panic and cannot be resumed.".to_string(),
+        mlua::Error::SerializeError(message) => format!("Cannot serialize value. {}", message),
+        mlua::Error::DeserializeError(message) => format!("Cannot deserialize value. {}", message),
+        mlua::Error::ExternalError(e) => e.to_string(),
+        mlua::Error::WithContext { context, cause } => format!("{}. {}", context, describe_mlua_message(cause)),
+        _ => e.to_string(),
     }
-    Ok(None)
-}
\ No newline at end of file
+}
+
+fn with_details(message: String, details: &Option<String>) -> String {
+    match details {
+        Some(details) => format!("{} {}", message, capitalize(details)),
+        None => message,
+    }
+}
+
+fn capitalize(value: &str) -> String {
+    let mut chars = value.chars();
+    match chars.next() {
+        Some(first) => format!("{}{}.", first.to_uppercase(), chars.as_str().trim_end_matches('.')),
+        None => String::new(),
+    }
+}
+
+/// Separates the traceback Luau appends to runtime errors from the message itself.
+fn split_traceback(message: &str) -> (String, Option<String>) {
+    match message.split_once("\nstack traceback:\n") {
+        Some((message, traceback)) => (message.to_string(), Some(format!("stack traceback:\n{}", traceback))),
+        None => (message.to_string(), None),
+    }
+}
+
+/// Traceback of the deepest callback, which is the closest one to the failing Luau code.
+fn innermost_traceback(e: &mlua::Error) -> Option<String> {
+    match e {
+        mlua::Error::RuntimeError(message) => split_traceback(message).1,
+        mlua::Error::CallbackError { traceback, cause } => innermost_traceback(cause).or(Some(traceback.clone())),
+        mlua::Error::BadArgument { cause, .. } | mlua::Error::WithContext { cause, .. } => innermost_traceback(cause),
+        _ => None,
+    }
+}
@@ -0,0 +1,302 @@
+use ini::Ini;
+use serde_yaml::{Mapping, Number, Value};
+
+use crate::error::Error;
+use crate::types::AppResult;
+
+pub fn parse_json(source: &str) -> AppResult<Value> {
+    let json: serde_json::Value = serde_json::from_str(source).map_err(parse_error)?;
+    Ok(serde_yaml::to_value(json)?)
+}
+
+pub fn parse_toml(source: &str) -> AppResult<Value> {
+    let table: toml::Table = toml::from_str(source).map_err(parse_error)?;
+    Ok(toml_to_yaml(toml::Value::Table(table)))
+}
+
+pub fn stringify_toml(value: &Value) -> AppResult<String> {
+    match value {
+        Value::Mapping(_) => toml::to_string(value).map_err(format_error),
+        _ => Err(format_error("Only tables can be represented as TOML documents.")),
+    }
+}
+
+/// Parses `KEY=value` pairs. Like `dotenv` tools, `$NAME` and `${NAME}` are expanded in unquoted
+/// and double-quoted values, but only from the keys defined earlier in the same source: the
+/// environment of the process is never read, so sandboxed scripts cannot reach it.
+pub fn parse_dotenv(source: &str) -> AppResult<Value> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut mapping = Mapping::new();
+    let mut position = 0;
+    let mut line = 1;
+
+    while position < chars.len() {
+        let ch = chars[position];
+        if ch == '\n' {
+            line += 1;
+            position += 1;
+            continue;
+        }
+        if ch.is_whitespace() {
+            position += 1;
+            continue;
+        }
+        if ch == '#' {
+            position = end_of_line(&chars, position);
+            continue;
+        }
+
+        let end = end_of_line(&chars, position);
+        let Some(separator) = chars[position..end].iter().position(|ch| *ch == '=').map(|i| position + i) else {
+            return Err(parse_error(format!("Line {}. Expected KEY=value.", line)));
+        };
+        let declaration: String = chars[position..separator].iter().collect();
+        let key = declaration.trim();
+        let key = key.strip_prefix("export ").map(|key| key.trim_start()).unwrap_or(key);
+        if key.is_empty() || !key.chars().all(|ch| ch.is_ascii_alphanumeric() || ch == '_' || ch == '.') {
+            return Err(parse_error(format!("Line {}. Invalid key: {}", line, key)));
+        }
+
+        position = separator + 1;
+        while position < chars.len() && (chars[position] == ' ' || chars[position] == '\t') {
+            position += 1;
+        }
+
+        let value = match chars.get(position) {
+            Some(quote @ ('\'' | '"')) => {
+                let start = position + 1;
+                let mut cursor = start;
+                while cursor < chars.len() && chars[cursor] != *quote {
+                    // Only double-quoted values have escapes
+                    cursor += match (*quote, chars[cursor]) {
+                        ('"', '\\') => 2,
+                        _ => 1,
+                    };
+                }
+                if cursor >= chars.len() {
+                    return Err(parse_error(format!("Line {}. Missing closing quote.", line)));
+                }
+                let raw: String = chars[start..cursor].iter().collect();
+                line += raw.matches('\n').count();
+                position = end_of_line(&chars, cursor + 1);
+                match quote {
+                    '"' => expand_dotenv_value(&raw, &mapping),
+                    _ => raw,
+                }
+            }
+            _ => {
+                let end = end_of_line(&chars, position);
+                let raw: String = chars[position..end].iter().collect();
+                position = end;
+                // A comment starts with `#` after whitespace
+                let raw = match raw.find(" #").or_else(|| raw.find("\t#")) {
+                    Some(comment) => &raw[..comment],
+                    None => raw.as_str(),
+                };
+                expand_dotenv_value(raw.trim_end(), &mapping)
+            }
+        };
+
+        mapping.insert(key.into(), value.into());
+    }
+    Ok(Value::Mapping(mapping))
+}
+
+fn end_of_line(chars: &[char], position: usize) -> usize {
+    chars[position..]
+        .iter()
+        .position(|ch| *ch == '\n')
+        .map(|i| position + i)
+        .unwrap_or(chars.len())
+}
+
+/// Resolves escapes and references to the keys parsed so far, unknown keys expand to nothing.
+fn expand_dotenv_value(raw: &str, mapping: &Mapping) -> String {
+    let mut output = String::new();
+    let mut chars = raw.chars().peekable();
+    while let Some(ch) = chars.next() {
+        match ch {
+            '\\' => match chars.next() {
+                Some('n') => output.push('\n'),
+                Some('r') => output.push('\r'),
+                Some('t') => output.push('\t'),
+                Some(other) => output.push(other),
+                None => output.push('\\'),
+            },
+            '$' => {
+                let braced = chars.next_if_eq(&'{').is_some();
+                let mut name = String::new();
+                while let Some(ch) = chars.next_if(|ch| ch.is_ascii_alphanumeric() || *ch == '_') {
+                    name.push(ch);
+                }
+                if braced {
+                    chars.next_if_eq(&'}');
+                } else if name.is_empty() {
+                    output.push('$');
+                    continue;
+                }
+                if let Some(Value::String(value)) = mapping.get(name.as_str()) {
+                    output.push_str(value);
+                }
+            }
+            _ => output.push(ch),
+        }
+    }
+    output
+}
+
+pub fn stringify_dotenv(value: &Value) -> AppResult<String> {
+    let mapping = value.as_mapping()
+        .ok_or_else(|| format_error("Only flat tables can be represented as dotenv files."))?;
+    let mut output = String::new();
+    for (key, value) in mapping {
+        output.push_str(&format!("{}={}\n", scalar_to_string(key)?, quote_dotenv(&scalar_to_string(value)?)));
+    }
+    Ok(output)
+}
+
+/// Properties outside of any section become top-level keys, every section becomes a nested table.
+pub fn parse_ini(source: &str) -> AppResult<Value> {
+    let ini = Ini::load_from_str(source).map_err(parse_error)?;
+    let mut mapping = Mapping::new();
+    for (section, properties) in ini.iter() {
+        let mut section_mapping = Mapping::new();
+        for (key, value) in properties.iter() {
+            section_mapping.insert(key.into(), value.into());
+        }
+        match section {
+            None => mapping.extend(section_mapping),
+            Some(name) => {
+                mapping.insert(name.into(), Value::Mapping(section_mapping));
+            }
+        }
+    }
+    Ok(Value::Mapping(mapping))
+}
+
+pub fn stringify_ini(value: &Value) -> AppResult<String> {
+    let mapping = value.as_mapping()
+        .ok_or_else(|| format_error("Only tables can be represented as INI files."))?;
+    let mut ini = Ini::new();
+    for (key, value) in mapping {
+        match value {
+            Value::Mapping(section) => {
+                let mut setter = ini.with_section(Some(scalar_to_string(key)?));
+                for (k, v) in section {
+                    setter.set(scalar_to_string(k)?, scalar_to_string(v)?);
+                }
+            }
+            _ => {
+                ini.with_general_section().set(scalar_to_string(key)?, scalar_to_string(value)?);
+            }
+        }
+    }
+    let mut output: Vec<u8> = vec![];
+    ini.write_to(&mut output)?;
+    String::from_utf8(output).map_err(format_error)
+}
+
+fn toml_to_yaml(value: toml::Value) -> Value {
+    match value {
+        toml::Value::String(s) => Value::String(s),
+        toml::Value::Integer(i) => Value::Number(Number::from(i)),
+        toml::Value::Float(f) => Value::Number(Number::from(f)),
+        toml::Value::Boolean(b) => Value::Bool(b),
+        toml::Value::Datetime(d) => Value::String(d.to_string()),
+        toml::Value::Array(a) => Value::Sequence(a.into_iter().map(toml_to_yaml).collect()),
+        toml::Value::Table(t) => Value::Mapping(t.into_iter().map(|(k, v)| (Value::String(k), toml_to_yaml(v))).collect()),
+    }
+}
+
+fn scalar_to_string(value: &Value) -> AppResult<String> {
+    match value {
+        Value::Null => Ok(String::new()),
+        Value::Bool(b) => Ok(b.to_string()),
+        Value::Number(n) => Ok(n.to_string()),
+        Value::String(s) => Ok(s.clone()),
+        _ => Err(format_error(format!("Expected a scalar, got: {}", serde_yaml::to_string(value)?.trim()))),
+    }
+}
+
+/// Parse errors of sources which are not read from a file, [`crate::storage::load_formatted`]
+/// names the file.
+fn parse_error<E: ToString>(e: E) -> Error {
+    Error::Parse { path: String::new(), message: e.to_string(), location: None, suggestion: None }
+}
+
+fn format_error<E: ToString>(e: E) -> Error {
+    Error::Format { message: e.to_string() }
+}
+
+fn quote_dotenv(value: &str) -> String {
+    let is_plain = value
+        .chars()
+        .all(|ch| ch.is_ascii_alphanumeric() || "_-./:@,+".contains(ch));
+    match is_plain {
+        true => value.to_string(),
+        false => {
+            let escaped = value
+                .replace('\\', "\\\\")
+                .replace('"', "\\\"")
+                .replace('$', "\\$")
+                .replace('\n', "\\n");
+            format!("\"{}\"", escaped)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error;
+
+    use serde_yaml::Value;
+
+    use crate::utils::formats::{parse_dotenv, parse_ini, parse_json, parse_toml, stringify_dotenv, stringify_ini, stringify_toml};
+
+    #[test]
+    fn toml_test() -> Result<(), Box<dyn Error>> {
+        let value = parse_toml("[package]\nname = \"app\"\nversion = \"0.1.0\"\nedition = 2021\nreleased = 2024-03-01\n")?;
+        assert_eq!(value["package"]["name"], Value::from("app"));
+        assert_eq!(value["package"]["edition"], Value::from(2021));
+        assert_eq!(value["package"]["released"], Value::from("2024-03-01"));
+        assert_eq!(parse_toml(&stringify_toml(&value)?)?, value);
+        assert!(stringify_toml(&Value::from("app")).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn json_test() -> Result<(), Box<dyn Error>> {
+        let value = parse_json(r#"{"name": "app", "ports": [80, 443], "main": null}"#)?;
+        assert_eq!(value["ports"][1], Value::from(443));
+        assert_eq!(value["main"], Value::Null);
+        Ok(())
+    }
+
+    #[test]
+    fn dotenv_test() -> Result<(), Box<dyn Error>> {
+        let value = parse_dotenv("# comment\nHOST=localhost\nGREETING=\"Hello, $USER_NAME\\\"s world\"\nPORT=8080\n")?;
+        assert_eq!(value["HOST"], Value::from("localhost"));
+        assert_eq!(value["PORT"], Value::from("8080"));
+        assert_eq!(parse_dotenv(&stringify_dotenv(&value)?)?, value);
+
+        // Only keys of the same file are expanded, never the environment of the process
+        let value = parse_dotenv("export NAME=app # comment\nHOME_DIR=${HOME}\nPATHS=$PATH\nURL=\"https://${NAME}.dev\"\nRAW='$NAME'\n")?;
+        assert_eq!(value["NAME"], Value::from("app"));
+        assert_eq!(value["HOME_DIR"], Value::from(""));
+        assert_eq!(value["PATHS"], Value::from(""));
+        assert_eq!(value["URL"], Value::from("https://app.dev"));
+        assert_eq!(value["RAW"], Value::from("$NAME"));
+        assert!(parse_dotenv("NAME\n").is_err());
+        assert!(parse_dotenv("NAME=\"app\n").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn ini_test() -> Result<(), Box<dyn Error>> {
+        let value = parse_ini("root = true\n\n[server]\nhost = localhost\nport = 8080\n")?;
+        assert_eq!(value["root"], Value::from("true"));
+        assert_eq!(value["server"]["port"], Value::from("8080"));
+        assert_eq!(parse_ini(&stringify_ini(&value)?)?, value);
+        Ok(())
+    }
+}
@@ -1,6 +1,8 @@
 use serde_yaml::Value;
 
 pub mod errors;
+pub mod formats;
+pub mod strings;
 
 pub fn merge_yaml(a: &mut Value, b: Value) {
     match (a, b) {
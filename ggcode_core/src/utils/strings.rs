@@ -0,0 +1,105 @@
+use convert_case::{Case, Casing};
+use pluralizer::pluralize;
+
+pub const DEFAULT_INDENT_PREFIX: &str = "    ";
+pub const DEFAULT_WRAP_WIDTH: usize = 80;
+
+/// Case conversions shared by the Tera filters (`snake_case`, ...) and the Luau `case` module (`case:snake`, ...).
+pub const CASES: [(&str, Case); 6] = [
+    ("snake", Case::Snake),
+    ("camel", Case::Camel),
+    ("pascal", Case::Pascal),
+    ("kebab", Case::Kebab),
+    ("screaming", Case::ScreamingSnake),
+    ("title", Case::Title),
+];
+
+pub fn to_case(value: &str, case: Case) -> String {
+    value.to_case(case)
+}
+
+pub fn plural(value: &str) -> String {
+    pluralize(value, 2, false)
+}
+
+pub fn singular(value: &str) -> String {
+    pluralize(value, 1, false)
+}
+
+/// Prefixes every line but the first one, following the semantics of the builtin Tera `indent` filter.
+pub fn indent(value: &str, prefix: &str, first: bool, blank: bool) -> String {
+    let mut out = String::with_capacity(value.len());
+    // Line endings are kept as they are, including the final one
+    for (i, line) in value.split_inclusive('\n').enumerate() {
+        if (i > 0 || first) && (blank || !line.trim_start().is_empty()) {
+            out.push_str(prefix);
+        }
+        out.push_str(line);
+    }
+    out
+}
+
+pub fn dedent(value: &str) -> String {
+    textwrap::dedent(value)
+}
+
+pub fn wrap(value: &str, width: usize) -> String {
+    textwrap::fill(value, width)
+}
+
+/// Picks the candidate closest to a misspelled name, if any of them is similar enough.
+pub fn suggest<'a, I: IntoIterator<Item = &'a str>>(value: &str, candidates: I) -> Option<String> {
+    candidates
+        .into_iter()
+        .map(|candidate| (strsim::jaro_winkler(value, candidate), candidate))
+        .filter(|(score, _)| *score > 0.8)
+        .max_by(|(a, _), (b, _)| a.total_cmp(b))
+        .map(|(_, candidate)| candidate.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use convert_case::Case;
+
+    use crate::utils::strings::{dedent, indent, plural, singular, suggest, to_case, wrap};
+
+    #[test]
+    fn to_case_test() {
+        assert_eq!(to_case("user account", Case::Snake), "user_account");
+        assert_eq!(to_case("user_account", Case::Camel), "userAccount");
+        assert_eq!(to_case("user-account", Case::Pascal), "UserAccount");
+        assert_eq!(to_case("UserAccount", Case::Kebab), "user-account");
+        assert_eq!(to_case("userAccount", Case::ScreamingSnake), "USER_ACCOUNT");
+        assert_eq!(to_case("user_account", Case::Title), "User Account");
+    }
+
+    #[test]
+    fn inflection_test() {
+        assert_eq!(plural("category"), "categories");
+        assert_eq!(plural("person"), "people");
+        assert_eq!(singular("categories"), "category");
+        assert_eq!(singular("people"), "person");
+    }
+
+    #[test]
+    fn indent_test() {
+        assert_eq!(indent("a\nb\n\nc", "  ", false, false), "a\n  b\n\n  c");
+        assert_eq!(indent("a\nb\n\nc", "  ", true, true), "  a\n  b\n  \n  c");
+        assert_eq!(indent("a\nb\n", "  ", false, false), "a\n  b\n");
+        assert_eq!(indent("a\r\nb\r\n", "  ", true, false), "  a\r\n  b\r\n");
+    }
+
+    #[test]
+    fn dedent_and_wrap_test() {
+        assert_eq!(dedent("    a\n      b\n"), "a\n  b\n");
+        assert_eq!(wrap("one two three", 7), "one two\nthree");
+    }
+
+    #[test]
+    fn suggest_test() {
+        let candidates = ["name", "version", "authors"];
+        assert_eq!(suggest("nmae", candidates), Some("name".to_string()));
+        assert_eq!(suggest("verison", candidates), Some("version".to_string()));
+        assert_eq!(suggest("license", candidates), None);
+    }
+}